@@ -0,0 +1,113 @@
+// cf. `ilog()` and `checked_ilog()` on the primitive integer types in `std`
+use num_traits::int::PrimInt;
+
+/// Compute the logarithm of `n` to the given base, rounded down, i.e., the
+/// largest integer `e` such that `base.pow(e) <= n`.
+///
+/// When `base` is a power of two, the result is computed directly from the
+/// bit length of `n`; otherwise, it is computed by repeated division.  Either
+/// way, the result is exact across the full range of `T`.
+///
+/// # Panics
+///
+/// Panics if `n` is not positive or if `base` is less than 2.
+pub fn ilog<T: PrimInt>(n: T, base: T) -> u32 {
+    assert!(n > T::zero(), "ilog: argument must be positive");
+    assert!(base > T::one(), "ilog: base must be at least 2");
+    if base.count_ones() == 1 {
+        let bits_per_digit = base.trailing_zeros();
+        let bit_length = T::zero().count_zeros() - n.leading_zeros();
+        (bit_length - 1) / bits_per_digit
+    } else {
+        let mut n = n;
+        let mut e = 0;
+        while n >= base {
+            n = n / base;
+            e += 1;
+        }
+        e
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn brute_ilog(n: u128, base: u128) -> u32 {
+        let mut e = 0;
+        while base.checked_pow(e + 1).is_some_and(|p| p <= n) {
+            e += 1;
+        }
+        e
+    }
+
+    #[rstest]
+    #[case(1, 2, 0)]
+    #[case(7, 2, 2)]
+    #[case(8, 2, 3)]
+    #[case(9, 3, 2)]
+    #[case(80, 3, 3)]
+    #[case(81, 3, 4)]
+    #[case(999, 10, 2)]
+    #[case(1000, 10, 3)]
+    #[case(u64::MAX, 2, 63)]
+    #[case(u64::MAX, 16, 15)]
+    #[case(u64::MAX, 10, 19)]
+    #[case(u64::MAX, u64::MAX, 1)]
+    #[case(u64::MAX - 1, u64::MAX, 0)]
+    fn test_ilog(#[case] n: u64, #[case] base: u64, #[case] e: u32) {
+        assert_eq!(ilog(n, base), e);
+    }
+
+    #[test]
+    fn test_ilog_signed() {
+        assert_eq!(ilog(i8::MAX, 2), 6);
+        assert_eq!(ilog(i8::MAX, 5), 3);
+        assert_eq!(ilog(i64::MAX, 8), 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "ilog: argument must be positive")]
+    fn ilog_zero() {
+        ilog(0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "ilog: base must be at least 2")]
+    fn ilog_base_one() {
+        ilog(10, 1);
+    }
+
+    #[test]
+    fn exhaustive_u8() {
+        for n in 1..=u8::MAX {
+            for base in 2..=u8::MAX {
+                assert_eq!(
+                    ilog(n, base),
+                    brute_ilog(u128::from(n), u128::from(base)),
+                    "n={n}, base={base}"
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_ilog_u64(n in 1u64.., base in 2u64..) {
+            prop_assert_eq!(ilog(n, base), brute_ilog(u128::from(n), u128::from(base)));
+        }
+
+        #[test]
+        fn prop_ilog_u64_small_base(n in 1u64.., base in 2u64..40) {
+            prop_assert_eq!(ilog(n, base), brute_ilog(u128::from(n), u128::from(base)));
+        }
+
+        #[test]
+        fn prop_ilog_u128_pow2_base(n in 1u128.., shift in 1u32..128) {
+            let base = 1u128 << shift;
+            prop_assert_eq!(ilog(n, base), brute_ilog(n, base));
+        }
+    }
+}
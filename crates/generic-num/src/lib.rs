@@ -1,4 +1,10 @@
 mod gcd;
+mod ilog;
 mod modinverse;
+mod ratio;
+mod roots;
 pub use crate::gcd::*;
+pub use crate::ilog::*;
 pub use crate::modinverse::*;
+pub use crate::ratio::*;
+pub use crate::roots::*;
@@ -0,0 +1,778 @@
+// cf. `Ratio` from the `num-rational` crate
+// cf. `Fraction` from Python's `fractions` module
+use crate::gcd::gcd_signed;
+use num_traits::int::PrimInt;
+use num_traits::ops::euclid::Euclid;
+use num_traits::sign::Signed;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An exact rational number with numerator & denominator of type `T`.
+///
+/// A `Ratio` is always stored in normalized form: the numerator and
+/// denominator are relatively prime, and the denominator is positive.  Zero
+/// is represented as `0/1`.  As a result, two `Ratio`s are equal if & only if
+/// their numerators and denominators are equal, and the derived `Hash` is
+/// consistent with equality.
+///
+/// Arithmetic is available both via `checked_*()` methods, which return
+/// `None` on overflow or division by zero, and via the standard operator
+/// traits, which panic in those cases.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ratio<T> {
+    numer: T,
+    denom: T,
+}
+
+impl<T: PrimInt + Euclid + Signed> Ratio<T> {
+    /// Construct the normalized ratio `numer/denom`.  Returns `None` if
+    /// `denom` is zero or if the normalized form is not representable in `T`
+    /// (e.g., `T::min_value()/-1`).
+    pub fn new(numer: T, denom: T) -> Option<Ratio<T>> {
+        let (numer, denom) = normalize(numer, denom)?;
+        Some(Ratio { numer, denom })
+    }
+
+    /// Construct the ratio `n/1`
+    pub fn from_integer(n: T) -> Ratio<T> {
+        Ratio {
+            numer: n,
+            denom: T::one(),
+        }
+    }
+
+    /// Return the ratio `0/1`
+    pub fn zero() -> Ratio<T> {
+        Ratio::from_integer(T::zero())
+    }
+
+    /// Return the ratio `1/1`
+    pub fn one() -> Ratio<T> {
+        Ratio::from_integer(T::one())
+    }
+
+    pub fn numer(&self) -> T {
+        self.numer
+    }
+
+    pub fn denom(&self) -> T {
+        self.denom
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numer.is_zero()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom.is_one()
+    }
+
+    /// Return the largest integer less than or equal to the ratio
+    pub fn floor(&self) -> T {
+        self.numer.div_euclid(&self.denom)
+    }
+
+    /// Return the smallest integer greater than or equal to the ratio
+    pub fn ceil(&self) -> T {
+        // If the ratio is not an integer, then `denom > 1`, so the floor is
+        // strictly less than the numerator and adding one can't overflow.
+        let floor = self.floor();
+        if self.is_integer() {
+            floor
+        } else {
+            floor + T::one()
+        }
+    }
+
+    /// Return the integer part of the ratio, rounded toward zero
+    pub fn trunc(&self) -> T {
+        self.numer / self.denom
+    }
+
+    /// Return `-self`, or `None` on overflow
+    pub fn checked_neg(&self) -> Option<Ratio<T>> {
+        Some(Ratio {
+            numer: checked_neg(self.numer)?,
+            denom: self.denom,
+        })
+    }
+
+    /// Return `1/self`, or `None` if `self` is zero or on overflow
+    pub fn checked_recip(&self) -> Option<Ratio<T>> {
+        match self.numer.cmp(&T::zero()) {
+            Ordering::Less => Some(Ratio {
+                numer: checked_neg(self.denom)?,
+                denom: checked_neg(self.numer)?,
+            }),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(Ratio {
+                numer: self.denom,
+                denom: self.numer,
+            }),
+        }
+    }
+
+    /// Return `self + other`, or `None` on overflow
+    pub fn checked_add(&self, other: &Ratio<T>) -> Option<Ratio<T>> {
+        let g = gcd_signed(self.denom, other.denom);
+        let numer = self
+            .numer
+            .checked_mul(&(other.denom / g))?
+            .checked_add(&other.numer.checked_mul(&(self.denom / g))?)?;
+        let denom = self.denom.checked_mul(&(other.denom / g))?;
+        Ratio::new(numer, denom)
+    }
+
+    /// Return `self - other`, or `None` on overflow
+    pub fn checked_sub(&self, other: &Ratio<T>) -> Option<Ratio<T>> {
+        let g = gcd_signed(self.denom, other.denom);
+        let numer = self
+            .numer
+            .checked_mul(&(other.denom / g))?
+            .checked_sub(&other.numer.checked_mul(&(self.denom / g))?)?;
+        let denom = self.denom.checked_mul(&(other.denom / g))?;
+        Ratio::new(numer, denom)
+    }
+
+    /// Return `self * other`, or `None` on overflow
+    pub fn checked_mul(&self, other: &Ratio<T>) -> Option<Ratio<T>> {
+        if self.is_zero() || other.is_zero() {
+            return Some(Ratio::zero());
+        }
+        // Cross-cancel first so that the result is already normalized and
+        // intermediate values stay as small as possible.
+        let g1 = gcd_with_denom(self.numer, other.denom);
+        let g2 = gcd_with_denom(other.numer, self.denom);
+        Some(Ratio {
+            numer: (self.numer / g1).checked_mul(&(other.numer / g2))?,
+            denom: (self.denom / g2).checked_mul(&(other.denom / g1))?,
+        })
+    }
+
+    /// Return `self / other`, or `None` if `other` is zero or on overflow
+    pub fn checked_div(&self, other: &Ratio<T>) -> Option<Ratio<T>> {
+        self.checked_mul(&other.checked_recip()?)
+    }
+
+    /// Return an iterator over the terms of the (finite, regular) continued
+    /// fraction expansion of the ratio.  The first term is the floor of the
+    /// ratio and may be zero or negative; all subsequent terms are positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib_generic_num::Ratio;
+    /// let r = Ratio::new(415, 93).unwrap();
+    /// assert_eq!(r.continued_fraction().collect::<Vec<_>>(), [4, 2, 6, 7]);
+    /// ```
+    pub fn continued_fraction(&self) -> ContinuedFraction<T> {
+        ContinuedFraction {
+            numer: self.numer,
+            denom: self.denom,
+        }
+    }
+
+    /// Construct a ratio from the terms of a finite continued fraction.
+    /// Returns `None` if `terms` is empty, if the result is not a valid
+    /// ratio, or on overflow.
+    pub fn from_continued_fraction<I: IntoIterator<Item = T>>(terms: I) -> Option<Ratio<T>> {
+        let (mut h0, mut h1) = (T::zero(), T::one());
+        let (mut k0, mut k1) = (T::one(), T::zero());
+        let mut nonempty = false;
+        for a in terms {
+            (h0, h1) = (h1, a.checked_mul(&h1)?.checked_add(&h0)?);
+            (k0, k1) = (k1, a.checked_mul(&k1)?.checked_add(&k0)?);
+            nonempty = true;
+        }
+        if nonempty { Ratio::new(h1, k1) } else { None }
+    }
+
+    /// Return the ratio closest to `self` whose denominator is at most
+    /// `max_denom`, i.e., the best rational approximation of `self` within
+    /// the given denominator bound.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib_generic_num::Ratio;
+    /// let pi: Ratio<i64> = "3.141592653589793".parse().unwrap();
+    /// assert_eq!(pi.approximate(10), Ratio::new(22, 7).unwrap());
+    /// assert_eq!(pi.approximate(1000), Ratio::new(355, 113).unwrap());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_denom` is less than 1.
+    pub fn approximate(&self, max_denom: T) -> Ratio<T> {
+        assert!(
+            max_denom >= T::one(),
+            "Ratio::approximate: max_denom must be at least 1"
+        );
+        if self.denom <= max_denom {
+            return *self;
+        }
+        // Walk the convergents p1/q1 of the continued fraction until the next
+        // one would have too large a denominator, and then pick between the
+        // last convergent and the best semiconvergent.  None of the values
+        // computed here can exceed the numerator or denominator of `self` in
+        // magnitude, so plain arithmetic can't overflow.
+        let (mut p0, mut q0, mut p1, mut q1) = (T::zero(), T::one(), T::one(), T::zero());
+        let (mut n, mut d) = (self.numer, self.denom);
+        loop {
+            let a = n.div_euclid(&d);
+            let q2 = match a.checked_mul(&q1).and_then(|aq| q0.checked_add(&aq)) {
+                Some(q2) if q2 <= max_denom => q2,
+                _ => break,
+            };
+            (p0, q0, p1, q1) = (p1, q1, p0 + a * p1, q2);
+            (n, d) = (d, n.rem_euclid(&d));
+        }
+        let k = (max_denom - q0) / q1;
+        let semi_denom = q0 + k * q1;
+        // The distance between the two candidates is `1/(q1 * semi_denom)`,
+        // while the distance from `p1/q1` to `self` is
+        // `d/(q1 * self.denom)`, so the convergent wins when
+        // `2 * d * semi_denom <= self.denom`.
+        let convergent_closer = d
+            .checked_mul(&semi_denom)
+            .and_then(|x| x.checked_add(&x))
+            .is_some_and(|x| x <= self.denom);
+        if convergent_closer {
+            Ratio {
+                numer: p1,
+                denom: q1,
+            }
+        } else {
+            Ratio {
+                numer: p0 + k * p1,
+                denom: semi_denom,
+            }
+        }
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> From<T> for Ratio<T> {
+    fn from(n: T) -> Ratio<T> {
+        Ratio::from_integer(n)
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> Ord for Ratio<T> {
+    fn cmp(&self, other: &Ratio<T>) -> Ordering {
+        // Compare integer parts, then compare the fractional parts by
+        // comparing their reciprocals (in reverse), and so on.  This avoids
+        // the overflow that cross-multiplication would risk.
+        let (mut a, mut b) = (self.numer, self.denom);
+        let (mut c, mut d) = (other.numer, other.denom);
+        let mut reversed = false;
+        loop {
+            let ord = match a.div_euclid(&b).cmp(&c.div_euclid(&d)) {
+                Ordering::Equal => {
+                    let r1 = a.rem_euclid(&b);
+                    let r2 = c.rem_euclid(&d);
+                    match (r1.is_zero(), r2.is_zero()) {
+                        (true, true) => Ordering::Equal,
+                        (true, false) => Ordering::Less,
+                        (false, true) => Ordering::Greater,
+                        (false, false) => {
+                            (a, b, c, d) = (b, r1, d, r2);
+                            reversed = !reversed;
+                            continue;
+                        }
+                    }
+                }
+                ord => ord,
+            };
+            return if reversed { ord.reverse() } else { ord };
+        }
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> PartialOrd for Ratio<T> {
+    fn partial_cmp(&self, other: &Ratio<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> Neg for Ratio<T> {
+    type Output = Ratio<T>;
+
+    fn neg(self) -> Ratio<T> {
+        self.checked_neg()
+            .expect("attempt to negate ratio with overflow")
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> Add for Ratio<T> {
+    type Output = Ratio<T>;
+
+    fn add(self, other: Ratio<T>) -> Ratio<T> {
+        self.checked_add(&other)
+            .expect("attempt to add ratios with overflow")
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> Sub for Ratio<T> {
+    type Output = Ratio<T>;
+
+    fn sub(self, other: Ratio<T>) -> Ratio<T> {
+        self.checked_sub(&other)
+            .expect("attempt to subtract ratios with overflow")
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> Mul for Ratio<T> {
+    type Output = Ratio<T>;
+
+    fn mul(self, other: Ratio<T>) -> Ratio<T> {
+        self.checked_mul(&other)
+            .expect("attempt to multiply ratios with overflow")
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> Div for Ratio<T> {
+    type Output = Ratio<T>;
+
+    fn div(self, other: Ratio<T>) -> Ratio<T> {
+        self.checked_div(&other)
+            .expect("attempt to divide ratio by zero or with overflow")
+    }
+}
+
+/// A `Ratio` is displayed as `numer/denom`, or as just `numer` if the
+/// denominator is 1.
+impl<T: PrimInt + Euclid + Signed + fmt::Display> fmt::Display for Ratio<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denom.is_one() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// Parses a ratio from either a fraction of the form `[+-]a/b` (e.g.,
+/// `"-3/4"`) or a decimal number of the form `[+-]digits[.digits]` (e.g.,
+/// `"-0.75"`).  The result is normalized.
+impl<T: PrimInt + Euclid + Signed> FromStr for Ratio<T> {
+    type Err = ParseRatioError;
+
+    fn from_str(s: &str) -> Result<Ratio<T>, ParseRatioError> {
+        let (numer, denom) = if let Some((n, d)) = s.split_once('/') {
+            let numer = parse_int(n)?;
+            if d.starts_with(['+', '-']) {
+                return Err(ParseRatioError::InvalidDigit);
+            }
+            (numer, parse_int::<T>(d)?)
+        } else {
+            let (negative, digits) = strip_sign(s);
+            let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+            if int_part.is_empty() && frac_part.is_empty() {
+                return Err(if s.is_empty() {
+                    ParseRatioError::Empty
+                } else {
+                    ParseRatioError::InvalidDigit
+                });
+            }
+            // Trailing zeros don't change the value, but they would inflate
+            // the denominator and could make it overflow.
+            let frac_part = frac_part.trim_end_matches('0');
+            let mut numer = T::zero();
+            let mut denom = T::one();
+            let ten = T::from(10).ok_or(ParseRatioError::Overflow)?;
+            for ch in int_part.chars().chain(frac_part.chars()) {
+                numer = push_digit(numer, ch, negative)?;
+            }
+            for _ in 0..frac_part.len() {
+                denom = denom.checked_mul(&ten).ok_or(ParseRatioError::Overflow)?;
+            }
+            (numer, denom)
+        };
+        if denom.is_zero() {
+            return Err(ParseRatioError::ZeroDenominator);
+        }
+        Ratio::new(numer, denom).ok_or(ParseRatioError::Overflow)
+    }
+}
+
+/// Error returned when parsing a [`Ratio`] from a string fails
+#[derive(Clone, Copy, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ParseRatioError {
+    #[error("cannot parse ratio from empty string")]
+    Empty,
+    #[error("invalid digit found in string")]
+    InvalidDigit,
+    #[error("denominator is zero")]
+    ZeroDenominator,
+    #[error("number too large to fit in target type")]
+    Overflow,
+}
+
+/// Iterator over the terms of a ratio's continued fraction expansion
+///
+/// This struct is returned by [`Ratio::continued_fraction()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContinuedFraction<T> {
+    numer: T,
+    denom: T,
+}
+
+impl<T: PrimInt + Euclid + Signed> Iterator for ContinuedFraction<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.denom.is_zero() {
+            return None;
+        }
+        let a = self.numer.div_euclid(&self.denom);
+        (self.numer, self.denom) = (self.denom, self.numer.rem_euclid(&self.denom));
+        Some(a)
+    }
+}
+
+impl<T: PrimInt + Euclid + Signed> FusedIterator for ContinuedFraction<T> {}
+
+/// Reduce `numer/denom` to lowest terms with a positive denominator, taking
+/// care not to overflow on `T::min_value()`.
+fn normalize<T: PrimInt + Euclid + Signed>(mut numer: T, mut denom: T) -> Option<(T, T)> {
+    if denom.is_zero() {
+        return None;
+    }
+    if numer.is_zero() {
+        return Some((T::zero(), T::one()));
+    }
+    let min = T::min_value();
+    if (numer == min || denom == min) && is_even(numer) && is_even(denom) {
+        numer = numer / (T::one() + T::one());
+        denom = denom / (T::one() + T::one());
+    }
+    // If either value is still the minimum, the other one is odd, and so the
+    // GCD is 1.
+    if denom == min {
+        return None;
+    }
+    if numer != min {
+        let g = gcd_signed(numer % denom, denom);
+        numer = numer / g;
+        denom = denom / g;
+    }
+    if denom < T::zero() {
+        numer = checked_neg(numer)?;
+        denom = -denom;
+    }
+    Some((numer, denom))
+}
+
+/// Compute the GCD of `n` and the positive integer `denom` without
+/// overflowing if `n` is `T::min_value()`
+fn gcd_with_denom<T: PrimInt + Euclid + Signed>(n: T, denom: T) -> T {
+    gcd_signed(n % denom, denom)
+}
+
+fn checked_neg<T: PrimInt>(n: T) -> Option<T> {
+    T::zero().checked_sub(&n)
+}
+
+fn is_even<T: PrimInt>(n: T) -> bool {
+    (n & T::one()).is_zero()
+}
+
+fn strip_sign(s: &str) -> (bool, &str) {
+    if let Some(t) = s.strip_prefix('-') {
+        (true, t)
+    } else {
+        (false, s.strip_prefix('+').unwrap_or(s))
+    }
+}
+
+fn parse_int<T: PrimInt + Signed>(s: &str) -> Result<T, ParseRatioError> {
+    if s.is_empty() {
+        return Err(ParseRatioError::Empty);
+    }
+    let (negative, digits) = strip_sign(s);
+    if digits.is_empty() {
+        return Err(ParseRatioError::InvalidDigit);
+    }
+    digits
+        .chars()
+        .try_fold(T::zero(), |acc, ch| push_digit(acc, ch, negative))
+}
+
+/// Append the decimal digit `ch` to `acc`.  If `negative` is true, the digit
+/// is subtracted rather than added so that `T::min_value()` can be reached.
+fn push_digit<T: PrimInt + Signed>(acc: T, ch: char, negative: bool) -> Result<T, ParseRatioError> {
+    let d = ch.to_digit(10).ok_or(ParseRatioError::InvalidDigit)?;
+    let d = T::from(d).ok_or(ParseRatioError::Overflow)?;
+    let ten = T::from(10).ok_or(ParseRatioError::Overflow)?;
+    let acc = acc.checked_mul(&ten).ok_or(ParseRatioError::Overflow)?;
+    if negative {
+        acc.checked_sub(&d)
+    } else {
+        acc.checked_add(&d)
+    }
+    .ok_or(ParseRatioError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn ratio(numer: i64, denom: i64) -> Ratio<i64> {
+        Ratio::new(numer, denom).unwrap()
+    }
+
+    #[rstest]
+    #[case(6, 4, Some((3, 2)))]
+    #[case(-6, 4, Some((-3, 2)))]
+    #[case(6, -4, Some((-3, 2)))]
+    #[case(-6, -4, Some((3, 2)))]
+    #[case(0, -4, Some((0, 1)))]
+    #[case(5, 0, None)]
+    #[case(i8::MIN, 1, Some((i8::MIN, 1)))]
+    #[case(i8::MIN, -1, None)]
+    #[case(i8::MIN, -2, Some((64, 1)))]
+    #[case(i8::MIN, 3, Some((i8::MIN, 3)))]
+    #[case(i8::MIN, -3, None)]
+    #[case(i8::MIN, i8::MIN, Some((1, 1)))]
+    #[case(64, i8::MIN, Some((-1, 2)))]
+    #[case(3, i8::MIN, None)]
+    #[case(i8::MAX, i8::MIN, None)]
+    #[case(i8::MIN, i8::MAX, Some((i8::MIN, i8::MAX)))]
+    fn test_new(#[case] numer: i8, #[case] denom: i8, #[case] parts: Option<(i8, i8)>) {
+        let r = Ratio::new(numer, denom);
+        assert_eq!(r.map(|r| (r.numer(), r.denom())), parts);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(ratio(1, 2) + ratio(1, 3), ratio(5, 6));
+        assert_eq!(ratio(1, 2) - ratio(1, 3), ratio(1, 6));
+        assert_eq!(ratio(1, 6) + ratio(1, 3), ratio(1, 2));
+        assert_eq!(ratio(2, 3) * ratio(9, 4), ratio(3, 2));
+        assert_eq!(ratio(2, 3) / ratio(-4, 9), ratio(-3, 2));
+        assert_eq!(ratio(2, 3) * Ratio::zero(), Ratio::zero());
+        assert_eq!(-ratio(2, 3), ratio(-2, 3));
+        assert_eq!(ratio(-2, 3).checked_recip(), Some(ratio(-3, 2)));
+    }
+
+    #[test]
+    fn test_checked_overflow() {
+        let max = Ratio::from_integer(i8::MAX);
+        let min = Ratio::from_integer(i8::MIN);
+        assert_eq!(max.checked_add(&Ratio::one()), None);
+        assert_eq!(min.checked_sub(&Ratio::one()), None);
+        assert_eq!(min.checked_neg(), None);
+        assert_eq!(min.checked_recip(), None);
+        assert_eq!(max.checked_mul(&Ratio::from_integer(2)), None);
+        assert_eq!(max.checked_div(&Ratio::zero()), None);
+        assert_eq!(
+            Ratio::<i8>::new(1, 100)
+                .unwrap()
+                .checked_add(&Ratio::new(1, 99).unwrap()),
+            None
+        );
+        assert_eq!(
+            min.checked_mul(&Ratio::new(1, 2).unwrap()),
+            Some(Ratio::from_integer(-64))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to add ratios with overflow")]
+    fn add_overflow() {
+        let _ = Ratio::from_integer(i8::MAX) + Ratio::one();
+    }
+
+    #[rstest]
+    #[case(ratio(7, 2), 3, 4, 3)]
+    #[case(ratio(-7, 2), -4, -3, -3)]
+    #[case(ratio(6, 1), 6, 6, 6)]
+    #[case(ratio(-6, 1), -6, -6, -6)]
+    fn test_rounding(
+        #[case] r: Ratio<i64>,
+        #[case] floor: i64,
+        #[case] ceil: i64,
+        #[case] trunc: i64,
+    ) {
+        assert_eq!(r.floor(), floor);
+        assert_eq!(r.ceil(), ceil);
+        assert_eq!(r.trunc(), trunc);
+    }
+
+    #[test]
+    fn test_cmp() {
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert!(ratio(-1, 2) < ratio(-1, 3));
+        assert!(ratio(2, 1) > ratio(3, 2));
+        assert!(ratio(5, 8) > ratio(3, 5));
+        assert!(ratio(5, 7) < ratio(12, 16));
+        assert_eq!(ratio(2, 4).cmp(&ratio(1, 2)), Ordering::Equal);
+        assert!(Ratio::new(i8::MAX, 126).unwrap() < Ratio::new(126, 125).unwrap());
+        assert!(Ratio::new(i8::MIN, 127).unwrap() < Ratio::new(-127, 127).unwrap());
+    }
+
+    #[rstest]
+    #[case(ratio(3, 4), "3/4")]
+    #[case(ratio(-3, 4), "-3/4")]
+    #[case(ratio(8, 4), "2")]
+    #[case(Ratio::zero(), "0")]
+    fn test_display(#[case] r: Ratio<i64>, #[case] s: &str) {
+        assert_eq!(r.to_string(), s);
+    }
+
+    #[rstest]
+    #[case("3/4", Ok(ratio(3, 4)))]
+    #[case("-6/8", Ok(ratio(-3, 4)))]
+    #[case("+6/8", Ok(ratio(3, 4)))]
+    #[case("42", Ok(ratio(42, 1)))]
+    #[case("-0.75", Ok(ratio(-3, 4)))]
+    #[case("1.50", Ok(ratio(3, 2)))]
+    #[case(".5", Ok(ratio(1, 2)))]
+    #[case("5.", Ok(ratio(5, 1)))]
+    #[case("0.000", Ok(ratio(0, 1)))]
+    #[case("0.50000000000000000000", Ok(ratio(1, 2)))]
+    #[case("-12.0000000000000000000000", Ok(ratio(-12, 1)))]
+    #[case("", Err(ParseRatioError::Empty))]
+    #[case("3/", Err(ParseRatioError::Empty))]
+    #[case("/3", Err(ParseRatioError::Empty))]
+    #[case("-", Err(ParseRatioError::InvalidDigit))]
+    #[case(".", Err(ParseRatioError::InvalidDigit))]
+    #[case("1/-2", Err(ParseRatioError::InvalidDigit))]
+    #[case("1.2.3", Err(ParseRatioError::InvalidDigit))]
+    #[case("1/2/3", Err(ParseRatioError::InvalidDigit))]
+    #[case("1/2.5", Err(ParseRatioError::InvalidDigit))]
+    #[case(" 1/2", Err(ParseRatioError::InvalidDigit))]
+    #[case("1/0", Err(ParseRatioError::ZeroDenominator))]
+    #[case("1/00", Err(ParseRatioError::ZeroDenominator))]
+    #[case("9223372036854775808", Err(ParseRatioError::Overflow))]
+    #[case("0.1234567890123456789", Err(ParseRatioError::Overflow))]
+    fn test_parse(#[case] s: &str, #[case] r: Result<Ratio<i64>, ParseRatioError>) {
+        assert_eq!(s.parse::<Ratio<i64>>(), r);
+    }
+
+    #[test]
+    fn test_parse_extremes() {
+        assert_eq!(
+            "-128".parse::<Ratio<i8>>(),
+            Ok(Ratio::from_integer(i8::MIN))
+        );
+        assert_eq!(
+            "-1.28".parse::<Ratio<i8>>(),
+            Ok(Ratio::new(-32, 25).unwrap())
+        );
+        assert_eq!("128".parse::<Ratio<i8>>(), Err(ParseRatioError::Overflow));
+        assert_eq!(
+            "-128/-1".parse::<Ratio<i8>>(),
+            Err(ParseRatioError::InvalidDigit)
+        );
+    }
+
+    #[rstest]
+    #[case(ratio(415, 93), vec![4, 2, 6, 7])]
+    #[case(ratio(-415, 93), vec![-5, 1, 1, 6, 7])]
+    #[case(ratio(3, 1), vec![3])]
+    #[case(ratio(0, 1), vec![0])]
+    #[case(ratio(1, 3), vec![0, 3])]
+    fn test_continued_fraction(#[case] r: Ratio<i64>, #[case] terms: Vec<i64>) {
+        assert_eq!(r.continued_fraction().collect::<Vec<_>>(), terms);
+        assert_eq!(Ratio::from_continued_fraction(terms), Some(r));
+    }
+
+    #[test]
+    fn test_from_continued_fraction_invalid() {
+        assert_eq!(Ratio::<i64>::from_continued_fraction([]), None);
+        assert_eq!(Ratio::<i64>::from_continued_fraction([1, 0]), None);
+        assert_eq!(Ratio::<i8>::from_continued_fraction([100, 2]), None);
+    }
+
+    #[rstest]
+    #[case("3.141592653589793", 10, ratio(22, 7))]
+    #[case("3.141592653589793", 100, ratio(311, 99))]
+    #[case("3.141592653589793", 1000, ratio(355, 113))]
+    #[case("-3.141592653589793", 1000, ratio(-355, 113))]
+    #[case("0.1", 1, ratio(0, 1))]
+    #[case("0.5", 1, ratio(0, 1))]
+    #[case("0.6", 1, ratio(1, 1))]
+    #[case("1/3", 2, ratio(1, 2))]
+    #[case("1/3", 3, ratio(1, 3))]
+    fn test_approximate(#[case] s: &str, #[case] max_denom: i64, #[case] r: Ratio<i64>) {
+        assert_eq!(s.parse::<Ratio<i64>>().unwrap().approximate(max_denom), r);
+    }
+
+    #[test]
+    fn approximate_extremes() {
+        let r = Ratio::new(i8::MIN, 127).unwrap();
+        assert_eq!(r.approximate(100), Ratio::new(-101, 100).unwrap());
+        let r = Ratio::new(i8::MIN, 3).unwrap();
+        assert_eq!(r.approximate(2), Ratio::new(-85, 2).unwrap());
+    }
+
+    fn arb_ratio() -> impl Strategy<Value = Ratio<i64>> {
+        (any::<i64>(), any::<i64>()).prop_filter_map("unrepresentable", |(n, d)| Ratio::new(n, d))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_normalized(r in arb_ratio()) {
+            prop_assert!(r.denom() > 0);
+            prop_assert_eq!(gcd_with_denom(r.numer(), r.denom()), 1);
+        }
+
+        #[test]
+        fn prop_cmp(a in arb_ratio(), b in arb_ratio()) {
+            let lhs = i128::from(a.numer()) * i128::from(b.denom());
+            let rhs = i128::from(b.numer()) * i128::from(a.denom());
+            prop_assert_eq!(a.cmp(&b), lhs.cmp(&rhs));
+        }
+
+        #[test]
+        fn prop_arithmetic(an in -1000i64..1000, ad in 1i64..1000, bn in -1000i64..1000, bd in 1i64..1000) {
+            let a = ratio(an, ad);
+            let b = ratio(bn, bd);
+            prop_assert_eq!(a + b, ratio(an * bd + bn * ad, ad * bd));
+            prop_assert_eq!(a - b, ratio(an * bd - bn * ad, ad * bd));
+            prop_assert_eq!(a * b, ratio(an * bn, ad * bd));
+            if bn != 0 {
+                prop_assert_eq!(a / b, ratio(an * bd, ad * bn));
+            }
+        }
+
+        #[test]
+        fn prop_display_parse_roundtrip(r in arb_ratio()) {
+            prop_assert_eq!(r.to_string().parse::<Ratio<i64>>(), Ok(r));
+        }
+
+        #[test]
+        fn prop_continued_fraction_roundtrip(r in arb_ratio()) {
+            prop_assert_eq!(Ratio::from_continued_fraction(r.continued_fraction()), Some(r));
+        }
+
+        #[test]
+        fn prop_approximate(n in -10000i64..10000, d in 1i64..10000, max_denom in 1i64..200) {
+            let r = ratio(n, d);
+            let approx = r.approximate(max_denom);
+            prop_assert!(approx.denom() <= max_denom);
+            // Brute force: for each allowed denominator, the best numerator
+            // is the floor or ceiling of `r * q`.
+            let dist = |x: Ratio<i64>| {
+                let diff = x - r;
+                if diff < Ratio::zero() { -diff } else { diff }
+            };
+            let best = (1..=max_denom)
+                .flat_map(|q| {
+                    let rq = r * Ratio::from_integer(q);
+                    [ratio(rq.floor(), q), ratio(rq.ceil(), q)]
+                })
+                .map(dist)
+                .min()
+                .unwrap();
+            prop_assert_eq!(dist(approx), best);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+// cf. `Roots` from the `num-integer` crate
+// cf. `isqrt()` on the primitive integer types in `std`
+use num_traits::int::PrimInt;
+use num_traits::pow::checked_pow;
+
+/// Compute the integer square root of `n`, i.e., the largest integer `r` such
+/// that `r * r <= n`.
+///
+/// This uses Newton's method entirely in integer arithmetic, and so it is
+/// exact across the full range of `T`.
+///
+/// # Panics
+///
+/// Panics if `n` is negative.
+pub fn isqrt<T: PrimInt>(n: T) -> T {
+    assert!(n >= T::zero(), "isqrt: argument must be nonnegative");
+    if n < two() {
+        return n;
+    }
+    // Start with a power of two that is guaranteed to be at least the square
+    // root; the Newton iterates then decrease monotonically until they reach
+    // the floor of the root.
+    let mut x = T::one() << (bit_length(n).div_ceil(2) as usize);
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Compute the integer cube root of `n`, i.e., the integer `r` with the
+/// largest magnitude such that `r * r * r` lies between zero and `n`
+/// (inclusive).  For negative `n`, the result is thus the cube root rounded
+/// toward zero.
+pub fn icbrt<T: PrimInt>(n: T) -> T {
+    nth_root(n, 3)
+}
+
+/// Compute the integer `k`-th root of `n`, i.e., the integer `r` with the
+/// largest magnitude such that `r.pow(k)` lies between zero and `n`
+/// (inclusive).  For negative `n` (and odd `k`), the result is thus the root
+/// rounded toward zero.
+///
+/// # Panics
+///
+/// Panics if `k` is zero or if `n` is negative and `k` is even.
+pub fn nth_root<T: PrimInt>(n: T, k: u32) -> T {
+    assert!(k > 0, "nth_root: root must be positive");
+    match k {
+        1 => n,
+        2 => isqrt(n),
+        _ if n >= T::zero() => nonneg_root(n, k),
+        _ => {
+            assert!(
+                k % 2 == 1,
+                "nth_root: cannot take an even root of a negative number"
+            );
+            // Negate `n + 1` rather than `n` so that `T::min_value()` doesn't
+            // overflow; the root of `|n|` is then either the root of `|n| - 1`
+            // or one more than that.
+            let r = nonneg_root(T::zero() - (n + T::one()), k);
+            let candidate = T::zero() - (r + T::one());
+            if checked_pow(candidate, k as usize) == Some(n) {
+                candidate
+            } else {
+                T::zero() - r
+            }
+        }
+    }
+}
+
+/// Test whether `n` is the square of an integer.  Negative numbers are never
+/// perfect squares.
+pub fn is_perfect_square<T: PrimInt>(n: T) -> bool {
+    if n < T::zero() {
+        return false;
+    }
+    // Squares are always congruent to 0, 1, 4, or 9 modulo 16, which lets us
+    // reject 75% of non-squares without taking a root.
+    let low = n & ((T::one() << 4) - T::one());
+    let (zero, one) = (T::zero(), T::one());
+    let (four, nine) = (one << 2, (one << 3) | one);
+    if low != zero && low != one && low != four && low != nine {
+        return false;
+    }
+    let r = isqrt(n);
+    r * r == n
+}
+
+/// Computes the `k`-th root of a nonnegative `n` one bit at a time, from the
+/// most significant bit that the root can have down to the least.
+fn nonneg_root<T: PrimInt>(n: T, k: u32) -> T {
+    if n < two() {
+        return n;
+    }
+    let mut root = T::zero();
+    for bit in (0..bit_length(n).div_ceil(k)).rev() {
+        let candidate = root | (T::one() << (bit as usize));
+        // An overflowing power is necessarily greater than `n`.
+        if checked_pow(candidate, k as usize).is_some_and(|p| p <= n) {
+            root = candidate;
+        }
+    }
+    root
+}
+
+/// Returns the number of bits needed to represent the nonnegative integer `n`
+fn bit_length<T: PrimInt>(n: T) -> u32 {
+    T::zero().count_zeros() - n.leading_zeros()
+}
+
+fn two<T: PrimInt>() -> T {
+    T::one() + T::one()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn brute_root(n: i128, k: u32) -> i128 {
+        let mut r = 0i128;
+        if n >= 0 {
+            while (r + 1).pow(k) <= n {
+                r += 1;
+            }
+        } else {
+            while (r - 1).pow(k) >= n {
+                r -= 1;
+            }
+        }
+        r
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 1)]
+    #[case(2, 1)]
+    #[case(3, 1)]
+    #[case(4, 2)]
+    #[case(15, 3)]
+    #[case(16, 4)]
+    #[case(17, 4)]
+    #[case(u64::MAX, u64::from(u32::MAX))]
+    fn test_isqrt(#[case] n: u64, #[case] r: u64) {
+        assert_eq!(isqrt(n), r);
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(7, 1)]
+    #[case(8, 2)]
+    #[case(26, 2)]
+    #[case(27, 3)]
+    #[case(-1, -1)]
+    #[case(-8, -2)]
+    #[case(-9, -2)]
+    #[case(i64::MAX, 2097151)]
+    #[case(i64::MIN, -2097152)]
+    fn test_icbrt(#[case] n: i64, #[case] r: i64) {
+        assert_eq!(icbrt(n), r);
+    }
+
+    #[rstest]
+    #[case(-128, 7, -2)]
+    #[case(-128, 3, -5)]
+    #[case(-127, 7, -1)]
+    #[case(127, 7, 1)]
+    #[case(-128, 1, -128)]
+    #[case(100, 100, 1)]
+    #[case(-100, 101, -1)]
+    fn test_nth_root_i8(#[case] n: i8, #[case] k: u32, #[case] r: i8) {
+        assert_eq!(nth_root(n, k), r);
+    }
+
+    #[test]
+    #[should_panic(expected = "isqrt: argument must be nonnegative")]
+    fn isqrt_negative() {
+        isqrt(-1);
+    }
+
+    #[test]
+    #[should_panic(expected = "nth_root: cannot take an even root of a negative number")]
+    fn nth_root_even_negative() {
+        nth_root(-16, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "nth_root: root must be positive")]
+    fn nth_root_zero() {
+        nth_root(16, 0);
+    }
+
+    #[test]
+    fn exhaustive_u8() {
+        for n in u8::MIN..=u8::MAX {
+            for k in 1..=9 {
+                assert_eq!(
+                    i128::from(nth_root(n, k)),
+                    brute_root(i128::from(n), k),
+                    "n={n}, k={k}"
+                );
+            }
+            let r = brute_root(i128::from(n), 2);
+            assert_eq!(is_perfect_square(n), r * r == i128::from(n), "n={n}");
+        }
+    }
+
+    #[test]
+    fn exhaustive_i8() {
+        for n in i8::MIN..=i8::MAX {
+            for k in 1..=9 {
+                if n < 0 && k % 2 == 0 {
+                    continue;
+                }
+                assert_eq!(
+                    i128::from(nth_root(n, k)),
+                    brute_root(i128::from(n), k),
+                    "n={n}, k={k}"
+                );
+            }
+            let r = brute_root(i128::from(n).abs(), 2);
+            assert_eq!(
+                is_perfect_square(n),
+                n >= 0 && r * r == i128::from(n),
+                "n={n}"
+            );
+        }
+    }
+
+    #[test]
+    fn exhaustive_i16_cbrt() {
+        for n in i16::MIN..=i16::MAX {
+            assert_eq!(i128::from(icbrt(n)), brute_root(i128::from(n), 3), "n={n}");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_isqrt_u64(n: u64) {
+            let r = u128::from(isqrt(n));
+            let n = u128::from(n);
+            prop_assert!(r * r <= n);
+            prop_assert!((r + 1) * (r + 1) > n);
+        }
+
+        #[test]
+        fn prop_isqrt_u128(n: u128) {
+            let r = isqrt(n);
+            prop_assert!(r * r <= n);
+            prop_assert!((r + 1).checked_mul(r + 1).is_none_or(|sq| sq > n));
+        }
+
+        #[test]
+        fn prop_is_perfect_square(r in 0u64..(1 << 32), delta: u64) {
+            prop_assert!(is_perfect_square(r * r));
+            // Every integer strictly between two consecutive squares is not a
+            // square.
+            prop_assert!(!is_perfect_square(r * r + delta % (2 * r + 1) + 1));
+        }
+
+        #[test]
+        fn prop_nth_root_i64(n: i64, k in 1u32..70) {
+            prop_assume!(n >= 0 || k % 2 == 1);
+            let r = i128::from(nth_root(n, k));
+            let n = i128::from(n);
+            if n >= 0 {
+                prop_assert!(r.pow(k) <= n);
+                prop_assert!((r + 1).checked_pow(k).is_none_or(|p| p > n));
+            } else {
+                prop_assert!(r.pow(k) >= n);
+                prop_assert!((r - 1).checked_pow(k).is_none_or(|p| p < n));
+            }
+        }
+    }
+}
@@ -0,0 +1,573 @@
+use crate::failure::{CommandFailure, cmdline};
+use crate::input::{Input, join_writer, spawn_writer};
+use crate::{ReadcmdError, decode_stdout, decode_stdout_lossy};
+use std::ffi::OsStr;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The number of lines of stderr to include in errors
+const STDERR_TAIL_LINES: usize = 10;
+
+/// The number of bytes at the end of each output stream that are retained
+/// even when the stream exceeds the output limit
+const TAIL_BYTES: usize = 4096;
+
+/// How often to check whether a command with a timeout has exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long to wait for a timed-out command's output streams to be closed
+/// after it is killed before giving up on reading them
+const READER_GRACE: Duration = Duration::from_secs(1);
+
+/// A builder for running a command with its stdout and stderr captured
+/// separately, optionally bounded by a timeout and a limit on the amount of
+/// output retained
+///
+/// If a timeout is set, then on Unix the command is run in a new process
+/// group, and the entire group is killed if the timeout expires.  Note that
+/// this means the command will not receive signals (such as Ctrl-C) sent to
+/// the terminal's foreground process group.  On other platforms, only the
+/// command itself is killed.
+#[derive(Debug)]
+pub struct Readcmd {
+    cmd: Command,
+    timeout: Option<Duration>,
+    output_limit: Option<usize>,
+    input: Option<Input>,
+}
+
+impl Readcmd {
+    pub fn new<S: AsRef<OsStr>>(arg0: S) -> Self {
+        Readcmd {
+            cmd: Command::new(arg0),
+            timeout: None,
+            output_limit: None,
+            input: None,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.cmd.arg(arg);
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.cmd.args(args);
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.cmd.current_dir(dir);
+        self
+    }
+
+    /// Kill the command if it has not exited and closed its output streams
+    /// after `timeout` has elapsed.  The output streams are included so that
+    /// background processes left holding them open (e.g., by `sleep 30 &`)
+    /// are subject to the timeout as well.
+    ///
+    /// On Unix, the command's entire process group is killed, but on other
+    /// platforms, only the command itself is, and any processes it started
+    /// are left running.  If such processes (or, on Unix, processes that
+    /// left the group) keep the output streams open after the command is
+    /// killed, then after a short grace period the output is no longer read,
+    /// and only what was captured up to that point is reported.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retain at most `limit` bytes of each of stdout and stderr.  Any
+    /// further output is read and discarded, and the corresponding
+    /// `*_truncated` field of [`CapturedOutput`] is set.
+    pub fn output_limit(&mut self, limit: usize) -> &mut Self {
+        self.output_limit = Some(limit);
+        self
+    }
+
+    /// Write `input` to the command's stdin.  As the input is consumed when
+    /// the command is run, it only applies to the next run; by default, the
+    /// command's stdin is null.
+    pub fn stdin(&mut self, input: Input) -> &mut Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Run the command and return its captured output.  If the command exits
+    /// unsuccessfully or times out, the returned error includes the last
+    /// few lines of its stderr.
+    pub fn output(&mut self) -> Result<CapturedOutput, ReadcmdError> {
+        let input = self.input.take();
+        self.cmd
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        if self.timeout.is_some() {
+            std::os::unix::process::CommandExt::process_group(&mut self.cmd, 0);
+        }
+        let start = Instant::now();
+        let mut child = self.cmd.spawn().map_err(|source| ReadcmdError::Startup {
+            cmdline: cmdline(&self.cmd),
+            source,
+        })?;
+        let writer = input.map(|input| spawn_writer(&mut child, input));
+        let limit = self.output_limit.unwrap_or(usize::MAX);
+        let stdout_reader = Reader::spawn(child.stdout.take(), limit);
+        let stderr_reader = Reader::spawn(child.stderr.take(), limit);
+        let readers = [&stdout_reader, &stderr_reader];
+        let deadline = self.timeout.map(|timeout| start + timeout);
+        let waited = wait(&mut child, deadline);
+        // The command may have left behind descendants that still hold its
+        // output streams open, so the readers are subject to the deadline as
+        // well.
+        let mut readers_timed_out = false;
+        if let Ok((_, false)) = waited
+            && let Some(deadline) = deadline
+            && !wait_for_readers(&readers, deadline)
+        {
+            kill(&mut child);
+            readers_timed_out = true;
+        }
+        let timed_out = readers_timed_out || matches!(waited, Ok((_, true)));
+        // Descendants that survived the kill (as happens on platforms without
+        // process groups) can keep the streams open indefinitely, in which
+        // case the readers are abandoned with whatever they've read so far.
+        let abandon = timed_out && !wait_for_readers(&readers, Instant::now() + READER_GRACE);
+        let stdout = stdout_reader.finish(abandon);
+        let stderr = stderr_reader.finish(abandon);
+        let (status, _) = waited.map_err(ReadcmdError::Wait)?;
+        // If the command timed out, the writer may be blocked reading from
+        // its input source, so we leave it to finish on its own.
+        if let Some(writer) = writer
+            && !timed_out
+        {
+            join_writer(writer).map_err(ReadcmdError::Write)?;
+        }
+        let stdout = stdout.map_err(ReadcmdError::Read)?;
+        let stderr = stderr.map_err(ReadcmdError::Read)?;
+        let elapsed = start.elapsed();
+        if status.success() && !timed_out {
+            return Ok(CapturedOutput {
+                status,
+                stdout: stdout.data,
+                stdout_truncated: stdout.truncated,
+                stderr: stderr.data,
+                stderr_truncated: stderr.truncated,
+                elapsed,
+            });
+        }
+        let mut failure = CommandFailure::new(&self.cmd, status);
+        failure.stderr_tail = stderr_tail(&stderr.tail);
+        if timed_out {
+            failure.timeout = self.timeout;
+            Err(ReadcmdError::Timeout(failure))
+        } else {
+            Err(ReadcmdError::Exit(failure))
+        }
+    }
+
+    /// Run the command and return its stdout, decoded as UTF-8 and with
+    /// leading & trailing whitespace removed
+    pub fn read(&mut self) -> Result<String, ReadcmdError> {
+        decode_stdout(self.output()?.stdout)
+    }
+
+    /// Like [`Readcmd::read()`], but invalid UTF-8 in the output is replaced
+    /// with U+FFFD REPLACEMENT CHARACTER
+    pub fn read_lossy(&mut self) -> Result<String, ReadcmdError> {
+        Ok(decode_stdout_lossy(self.output()?.stdout))
+    }
+}
+
+/// The output of a command that exited successfully, as returned by
+/// [`Readcmd::output()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CapturedOutput {
+    pub status: ExitStatus,
+
+    /// The command's stdout, up to the output limit
+    pub stdout: Vec<u8>,
+
+    /// Whether the command's stdout exceeded the output limit
+    pub stdout_truncated: bool,
+
+    /// The command's stderr, up to the output limit
+    pub stderr: Vec<u8>,
+
+    /// Whether the command's stderr exceeded the output limit
+    pub stderr_truncated: bool,
+
+    /// How long the command took to run
+    pub elapsed: Duration,
+}
+
+/// The data read from one of a command's output streams
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Captured {
+    data: Vec<u8>,
+    truncated: bool,
+    // The last `TAIL_BYTES` (or so) bytes of the stream, regardless of the
+    // output limit
+    tail: Vec<u8>,
+}
+
+/// A thread reading one of a command's output streams into a buffer that
+/// remains accessible if the thread has to be abandoned
+#[derive(Debug)]
+struct Reader {
+    handle: JoinHandle<io::Result<()>>,
+    captured: Arc<Mutex<Captured>>,
+}
+
+impl Reader {
+    fn spawn<R: Read + Send + 'static>(stream: Option<R>, limit: usize) -> Reader {
+        let captured = Arc::new(Mutex::new(Captured::default()));
+        let buffer = Arc::clone(&captured);
+        let handle = std::thread::spawn(move || match stream {
+            Some(stream) => capture(stream, limit, &buffer),
+            None => Ok(()),
+        });
+        Reader { handle, captured }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the thread to finish and return the data it read.  If
+    /// `abandon` is true, the thread is instead left running, and the data
+    /// read so far is returned.
+    fn finish(self, abandon: bool) -> io::Result<Captured> {
+        if !abandon {
+            match self.handle.join() {
+                Ok(r) => r?,
+                Err(barf) => std::panic::resume_unwind(barf),
+            }
+        }
+        let Ok(mut captured) = self.captured.lock() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        Ok(std::mem::take(&mut *captured))
+    }
+}
+
+/// Read all of `stream` into `captured`, retaining the first `limit` bytes
+/// and the last `TAIL_BYTES` bytes
+fn capture<R: Read>(mut stream: R, limit: usize, captured: &Mutex<Captured>) -> io::Result<()> {
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let chunk = &buf[..n];
+        let Ok(mut captured) = captured.lock() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        let room = limit.saturating_sub(captured.data.len());
+        if chunk.len() > room {
+            captured.truncated = true;
+        }
+        captured
+            .data
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+        captured.tail.extend_from_slice(chunk);
+        if captured.tail.len() > 2 * TAIL_BYTES {
+            let excess = captured.tail.len() - TAIL_BYTES;
+            captured.tail.drain(..excess);
+        }
+    }
+    Ok(())
+}
+
+/// Wait for `child` to exit, killing it if it has not exited by `deadline`.
+/// Returns the child's exit status along with whether it was killed.
+fn wait(child: &mut Child, deadline: Option<Instant>) -> io::Result<(ExitStatus, bool)> {
+    let Some(deadline) = deadline else {
+        return Ok((child.wait()?, false));
+    };
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, false));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            kill(child);
+            return Ok((child.wait()?, true));
+        }
+        std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Wait for all of `readers` to finish, giving up at `deadline`.  Returns
+/// `false` if any of them were still running at the deadline.
+fn wait_for_readers(readers: &[&Reader], deadline: Instant) -> bool {
+    loop {
+        if readers.iter().all(|r| r.is_finished()) {
+            return true;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Kill `child` along with the rest of its process group.  This also works
+/// if `child` has exited and been reaped but other members of its group are
+/// still running.
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    use rustix::process::{Pid, Signal, kill_process_group};
+    if kill_process_group(Pid::from_child(child), Signal::KILL).is_err() {
+        // The group may have already exited; try the child itself just in
+        // case.
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Returns the last `STDERR_TAIL_LINES` lines of `stderr`, or `None` if it
+/// is empty or all whitespace
+fn stderr_tail(stderr: &[u8]) -> Option<String> {
+    let s = String::from_utf8_lossy(stderr);
+    let s = s.trim_end();
+    if s.trim_start().is_empty() {
+        return None;
+    }
+    let start = s
+        .rmatch_indices('\n')
+        .nth(STDERR_TAIL_LINES - 1)
+        .map_or(0, |(i, _)| i + 1);
+    Some(s[start..].to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture_all(data: &[u8], limit: usize) -> Captured {
+        let captured = Mutex::new(Captured::default());
+        capture(data, limit, &captured).unwrap();
+        captured.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_capture_limit() {
+        let data = (0..20_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let captured = capture_all(&data, 100);
+        assert_eq!(captured.data, &data[..100]);
+        assert!(captured.truncated);
+        assert!(data.ends_with(&captured.tail));
+        assert!(captured.tail.len() >= TAIL_BYTES);
+    }
+
+    #[test]
+    fn test_capture_under_limit() {
+        let captured = capture_all(b"hello", 5);
+        assert_eq!(captured.data, b"hello");
+        assert!(!captured.truncated);
+        assert_eq!(captured.tail, b"hello");
+    }
+
+    #[test]
+    fn test_stderr_tail() {
+        assert_eq!(stderr_tail(b""), None);
+        assert_eq!(stderr_tail(b"  \n\n"), None);
+        assert_eq!(
+            stderr_tail(b"warning: foo\nerror: bar\n"),
+            Some(String::from("warning: foo\nerror: bar"))
+        );
+        let lines = (1..=15).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let many = lines.join("\n") + "\n";
+        let expected = lines[5..].join("\n");
+        assert_eq!(stderr_tail(many.as_bytes()), Some(expected));
+    }
+
+    #[cfg(unix)]
+    mod unix {
+        use super::*;
+
+        #[test]
+        fn test_output() {
+            let out = Readcmd::new("sh")
+                .args(["-c", "printf 'out\\n'; printf 'err\\n' >&2"])
+                .output()
+                .unwrap();
+            assert!(out.status.success());
+            assert_eq!(out.stdout, b"out\n");
+            assert_eq!(out.stderr, b"err\n");
+            assert!(!out.stdout_truncated);
+            assert!(!out.stderr_truncated);
+        }
+
+        #[test]
+        fn test_read() {
+            let out = Readcmd::new("printf")
+                .arg(r"  This text will be stripped.\n\n")
+                .read()
+                .unwrap();
+            assert_eq!(out, "This text will be stripped.");
+        }
+
+        #[test]
+        fn test_current_dir() {
+            let out = Readcmd::new("pwd").current_dir("/").read().unwrap();
+            assert_eq!(out, "/");
+            let r = Readcmd::new("false").current_dir("/").read();
+            let Err(ReadcmdError::Exit(failure)) = r else {
+                panic!("Command did not exit nonzero: {r:?}");
+            };
+            assert_eq!(failure.cwd.as_deref(), Some(Path::new("/")));
+            assert_eq!(
+                failure.to_string(),
+                "command `false` (in /) exited with status 1"
+            );
+        }
+
+        #[test]
+        fn test_output_limit() {
+            let out = Readcmd::new("sh")
+                .args(["-c", "yes | head -n 100000"])
+                .output_limit(10)
+                .output()
+                .unwrap();
+            assert_eq!(out.stdout, b"y\ny\ny\ny\ny\n");
+            assert!(out.stdout_truncated);
+            assert_eq!(out.stderr, b"");
+            assert!(!out.stderr_truncated);
+        }
+
+        #[test]
+        fn test_exit_stderr_tail() {
+            let r = Readcmd::new("sh")
+                .args([
+                    "-c",
+                    "echo starting >&2; echo 'fatal: no such ref' >&2; exit 3",
+                ])
+                .read();
+            let Err(ReadcmdError::Exit(failure)) = r else {
+                panic!("Command did not exit nonzero: {r:?}");
+            };
+            assert_eq!(failure.status.code(), Some(3));
+            assert_eq!(
+                failure.stderr_tail.as_deref(),
+                Some("starting\nfatal: no such ref")
+            );
+            assert!(
+                failure
+                    .to_string()
+                    .ends_with("exited with status 3: fatal: no such ref"),
+                "{failure}"
+            );
+        }
+
+        #[test]
+        fn test_timeout() {
+            let start = Instant::now();
+            // The background `sleep` holds the pipes open, so this only
+            // finishes promptly if the whole process group is killed.
+            let r = Readcmd::new("sh")
+                .args(["-c", "echo waiting >&2; sleep 30 & sleep 30"])
+                .timeout(Duration::from_millis(200))
+                .output();
+            let Err(ReadcmdError::Timeout(failure)) = r else {
+                panic!("Command did not time out: {r:?}");
+            };
+            assert_eq!(failure.timeout, Some(Duration::from_millis(200)));
+            assert_eq!(failure.signal_name(), Some("SIGKILL"));
+            assert_eq!(failure.stderr_tail.as_deref(), Some("waiting"));
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Killing the command took too long"
+            );
+        }
+
+        #[test]
+        fn test_timeout_background_process() {
+            let start = Instant::now();
+            // The shell exits immediately, but the background `sleep` keeps
+            // stdout & stderr open.
+            let r = Readcmd::new("sh")
+                .args(["-c", "echo leaving >&2; sleep 30 & exit 1"])
+                .timeout(Duration::from_millis(500))
+                .output();
+            let Err(ReadcmdError::Timeout(failure)) = r else {
+                panic!("Command did not time out: {r:?}");
+            };
+            assert_eq!(failure.timeout, Some(Duration::from_millis(500)));
+            assert_eq!(failure.status.code(), Some(1));
+            assert_eq!(failure.stderr_tail.as_deref(), Some("leaving"));
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Killing the background process took too long"
+            );
+        }
+
+        #[test]
+        fn test_timeout_escaped_process() {
+            let start = Instant::now();
+            // The background process leaves the command's process group (as
+            // all processes effectively do on non-Unix platforms) and so is
+            // not killed, but it keeps stdout & stderr open.
+            let r = Readcmd::new("sh")
+                .args([
+                    "-c",
+                    "echo leaving >&2; perl -MPOSIX -e 'POSIX::setsid(); sleep 5' & exit 1",
+                ])
+                .timeout(Duration::from_millis(300))
+                .output();
+            let Err(ReadcmdError::Timeout(failure)) = r else {
+                panic!("Command did not time out: {r:?}");
+            };
+            assert_eq!(failure.status.code(), Some(1));
+            assert_eq!(failure.stderr_tail.as_deref(), Some("leaving"));
+            assert!(
+                start.elapsed() < Duration::from_secs(4),
+                "Waiting for the escaped process's output took too long"
+            );
+        }
+
+        #[test]
+        fn test_stdin() {
+            let mut cmd = Readcmd::new("sort");
+            let out = cmd
+                .stdin(Input::lines(["pear", "apple", "fig"]))
+                .read()
+                .unwrap();
+            assert_eq!(out, "apple\nfig\npear");
+            // The input only applies to one run
+            assert_eq!(cmd.read().unwrap(), "");
+        }
+
+        #[test]
+        fn test_timeout_not_reached() {
+            let out = Readcmd::new("echo")
+                .arg("quick")
+                .timeout(Duration::from_secs(30))
+                .read()
+                .unwrap();
+            assert_eq!(out, "quick");
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+/// Information about a command that exited unsuccessfully
+///
+/// The `Display` implementation describes the failure on a single line,
+/// including the last line of stderr if available.  The alternate form
+/// (`{:#}`), also available via [`CommandFailure::detailed()`], spreads the
+/// description over multiple lines and includes all of `stderr_tail`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommandFailure {
+    /// The command and its arguments, shell-quoted and joined with spaces
+    pub cmdline: String,
+
+    /// The working directory that the command was run in, if one was set
+    pub cwd: Option<PathBuf>,
+
+    pub status: ExitStatus,
+
+    /// If the command was killed for running too long, the timeout that it
+    /// exceeded
+    pub timeout: Option<Duration>,
+
+    /// The last few lines of the command's stderr, if it was captured and
+    /// nonempty
+    pub stderr_tail: Option<String>,
+}
+
+impl CommandFailure {
+    pub(crate) fn new(cmd: &Command, status: ExitStatus) -> Self {
+        CommandFailure {
+            cmdline: cmdline(cmd),
+            cwd: cmd.get_current_dir().map(PathBuf::from),
+            status,
+            timeout: None,
+            stderr_tail: None,
+        }
+    }
+
+    /// Returns the number of the signal that terminated the command, if any.
+    /// This is always `None` on non-Unix platforms.
+    pub fn signal(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            std::os::unix::process::ExitStatusExt::signal(&self.status)
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    /// Returns the name (e.g., `"SIGKILL"`) of the signal that terminated
+    /// the command, if any and if known
+    pub fn signal_name(&self) -> Option<&'static str> {
+        self.signal().and_then(signal_name)
+    }
+
+    /// Returns a multi-line description of the failure
+    pub fn detailed(&self) -> DetailedFailure<'_> {
+        DetailedFailure(self)
+    }
+
+    fn write_summary(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "command `{}`", self.cmdline)?;
+        if !f.alternate()
+            && let Some(cwd) = &self.cwd
+        {
+            write!(f, " (in {})", cwd.display())?;
+        }
+        if let Some(timeout) = self.timeout {
+            write!(f, " timed out after {timeout:?}")
+        } else if let Some(code) = self.status.code() {
+            write!(f, " exited with status {code}")
+        } else if let Some(sig) = self.signal() {
+            write!(f, " was killed by signal {sig}")?;
+            if let Some(name) = signal_name(sig) {
+                write!(f, " ({name})")?;
+            }
+            Ok(())
+        } else {
+            write!(f, " failed: {}", self.status)
+        }
+    }
+}
+
+impl fmt::Display for CommandFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_summary(f)?;
+        if f.alternate() {
+            if let Some(cwd) = &self.cwd {
+                write!(f, "\n    working directory: {}", cwd.display())?;
+            }
+            if let Some(tail) = &self.stderr_tail {
+                write!(f, "\n    stderr:")?;
+                for line in tail.lines() {
+                    write!(f, "\n        {line}")?;
+                }
+            }
+        } else if let Some(last) = self.stderr_tail.as_deref().and_then(|s| s.lines().last()) {
+            write!(f, ": {}", last.trim())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CommandFailure {}
+
+/// A multi-line description of a [`CommandFailure`], as returned by
+/// [`CommandFailure::detailed()`]
+#[derive(Clone, Copy, Debug)]
+pub struct DetailedFailure<'a>(&'a CommandFailure);
+
+impl fmt::Display for DetailedFailure<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+/// Returns the program & arguments of `cmd`, shell-quoted and joined with
+/// spaces
+pub(crate) fn cmdline(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(quote_osstr)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote_osstr(s: &OsStr) -> String {
+    shell_words::quote(&s.to_string_lossy()).to_string()
+}
+
+#[cfg(unix)]
+fn signal_name(sig: i32) -> Option<&'static str> {
+    use rustix::process::Signal;
+    let name = match Signal::from_named_raw(sig)? {
+        Signal::HUP => "SIGHUP",
+        Signal::INT => "SIGINT",
+        Signal::QUIT => "SIGQUIT",
+        Signal::ILL => "SIGILL",
+        Signal::TRAP => "SIGTRAP",
+        Signal::ABORT => "SIGABRT",
+        Signal::BUS => "SIGBUS",
+        Signal::FPE => "SIGFPE",
+        Signal::KILL => "SIGKILL",
+        Signal::USR1 => "SIGUSR1",
+        Signal::SEGV => "SIGSEGV",
+        Signal::USR2 => "SIGUSR2",
+        Signal::PIPE => "SIGPIPE",
+        Signal::ALARM => "SIGALRM",
+        Signal::TERM => "SIGTERM",
+        Signal::CHILD => "SIGCHLD",
+        Signal::CONT => "SIGCONT",
+        Signal::STOP => "SIGSTOP",
+        Signal::TSTP => "SIGTSTP",
+        Signal::TTIN => "SIGTTIN",
+        Signal::TTOU => "SIGTTOU",
+        Signal::URG => "SIGURG",
+        Signal::XCPU => "SIGXCPU",
+        Signal::XFSZ => "SIGXFSZ",
+        Signal::VTALARM => "SIGVTALRM",
+        Signal::PROF => "SIGPROF",
+        Signal::WINCH => "SIGWINCH",
+        Signal::SYS => "SIGSYS",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(not(unix))]
+fn signal_name(_sig: i32) -> Option<&'static str> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmdline() {
+        let mut cmd = Command::new("git");
+        cmd.args(["commit", "-m", "Fix the thing's bug", ""]);
+        assert_eq!(cmdline(&cmd), r"git commit -m 'Fix the thing'\''s bug' ''");
+    }
+
+    #[cfg(unix)]
+    mod unix {
+        use super::*;
+        use std::os::unix::process::ExitStatusExt;
+
+        fn failure(status: ExitStatus) -> CommandFailure {
+            let mut cmd = Command::new("git");
+            cmd.args(["log", "--format=%H %s"]);
+            CommandFailure::new(&cmd, status)
+        }
+
+        #[test]
+        fn test_exit_code() {
+            let f = failure(ExitStatus::from_raw(3 << 8));
+            assert_eq!(f.signal(), None);
+            assert_eq!(f.signal_name(), None);
+            assert_eq!(
+                f.to_string(),
+                "command `git log '--format=%H %s'` exited with status 3"
+            );
+        }
+
+        #[test]
+        fn test_signal() {
+            let f = failure(ExitStatus::from_raw(9));
+            assert_eq!(f.signal(), Some(9));
+            assert_eq!(f.signal_name(), Some("SIGKILL"));
+            assert_eq!(
+                f.to_string(),
+                "command `git log '--format=%H %s'` was killed by signal 9 (SIGKILL)"
+            );
+        }
+
+        #[test]
+        fn test_timeout() {
+            let mut f = failure(ExitStatus::from_raw(9));
+            f.timeout = Some(Duration::from_secs(5));
+            assert_eq!(
+                f.to_string(),
+                "command `git log '--format=%H %s'` timed out after 5s"
+            );
+        }
+
+        #[test]
+        fn test_cwd_and_stderr() {
+            let mut f = failure(ExitStatus::from_raw(128 << 8));
+            f.cwd = Some(PathBuf::from("/src/project"));
+            f.stderr_tail = Some(String::from(
+                "warning: something odd\nfatal: not a git repository",
+            ));
+            assert_eq!(
+                f.to_string(),
+                "command `git log '--format=%H %s'` (in /src/project) exited with status 128: fatal: not a git repository"
+            );
+            assert_eq!(
+                f.detailed().to_string(),
+                concat!(
+                    "command `git log '--format=%H %s'` exited with status 128\n",
+                    "    working directory: /src/project\n",
+                    "    stderr:\n",
+                    "        warning: something odd\n",
+                    "        fatal: not a git repository",
+                )
+            );
+            assert_eq!(format!("{f:#}"), f.detailed().to_string());
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::RunError;
+use crate::failure::cmdline;
+use std::fmt;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::{Child, ChildStdin, Command, Output, Stdio};
+use std::thread::JoinHandle;
+
+/// Data to write to a command's stdin
+///
+/// The data is written from a separate thread while the command's output is
+/// being read, so a command that produces a lot of output before it has
+/// consumed all of its input cannot deadlock.  If the command exits or
+/// closes its stdin before all of the input has been written, the rest of
+/// the input is silently discarded.
+pub struct Input(InputInner);
+
+enum InputInner {
+    Bytes(Vec<u8>),
+    Reader(Box<dyn Read + Send>),
+    Lines(Box<dyn Iterator<Item = String> + Send>),
+}
+
+impl Input {
+    /// Write the given bytes to stdin
+    pub fn bytes<B: Into<Vec<u8>>>(bytes: B) -> Self {
+        Input(InputInner::Bytes(bytes.into()))
+    }
+
+    /// Copy everything from `reader` to stdin
+    pub fn reader<R: Read + Send + 'static>(reader: R) -> Self {
+        Input(InputInner::Reader(Box::new(reader)))
+    }
+
+    /// Write each string yielded by `lines` to stdin, each followed by a
+    /// newline
+    pub fn lines<I, S>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        I::IntoIter: Send + 'static,
+        S: Into<String> + 'static,
+    {
+        Input(InputInner::Lines(Box::new(
+            lines.into_iter().map(Into::into),
+        )))
+    }
+
+    /// Write the input to `stdin`, then close it
+    fn write_to(self, stdin: ChildStdin) -> io::Result<()> {
+        let mut stdin = BufWriter::new(stdin);
+        match self.0 {
+            InputInner::Bytes(bytes) => stdin.write_all(&bytes)?,
+            InputInner::Reader(mut reader) => {
+                io::copy(&mut reader, &mut stdin)?;
+            }
+            InputInner::Lines(lines) => {
+                for line in lines {
+                    stdin.write_all(line.as_bytes())?;
+                    stdin.write_all(b"\n")?;
+                }
+            }
+        }
+        stdin.flush()
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            InputInner::Bytes(bytes) => f.debug_tuple("Input::Bytes").field(bytes).finish(),
+            InputInner::Reader(_) => f.write_str("Input::Reader(..)"),
+            InputInner::Lines(_) => f.write_str("Input::Lines(..)"),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Input {
+    fn from(value: Vec<u8>) -> Input {
+        Input::bytes(value)
+    }
+}
+
+impl From<&[u8]> for Input {
+    fn from(value: &[u8]) -> Input {
+        Input::bytes(value)
+    }
+}
+
+impl From<String> for Input {
+    fn from(value: String) -> Input {
+        Input::bytes(value)
+    }
+}
+
+impl From<&str> for Input {
+    fn from(value: &str) -> Input {
+        Input::bytes(value)
+    }
+}
+
+/// Start a thread that writes `input` to the stdin of `child`, which must
+/// have been spawned with a piped stdin
+pub(crate) fn spawn_writer(child: &mut Child, input: Input) -> JoinHandle<io::Result<()>> {
+    let stdin = child.stdin.take();
+    std::thread::spawn(move || {
+        let Some(stdin) = stdin else {
+            return Ok(());
+        };
+        match input.write_to(stdin) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            r => r,
+        }
+    })
+}
+
+pub(crate) fn join_writer(handle: JoinHandle<io::Result<()>>) -> io::Result<()> {
+    match handle.join() {
+        Ok(r) => r,
+        Err(barf) => std::panic::resume_unwind(barf),
+    }
+}
+
+/// Run `cmd` with `input` written to its stdin, and wait for it to exit,
+/// collecting its output (if piped)
+pub(crate) fn output_with_input(cmd: &mut Command, input: Input) -> Result<Output, RunError> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|source| RunError::Startup {
+            cmdline: cmdline(cmd),
+            source,
+        })?;
+    let writer = spawn_writer(&mut child, input);
+    let output = child.wait_with_output();
+    let written = join_writer(writer);
+    let output = output.map_err(RunError::Wait)?;
+    written.map_err(RunError::Write)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(
+            format!("{:?}", Input::from("hi")),
+            "Input::Bytes([104, 105])"
+        );
+        assert_eq!(
+            format!("{:?}", Input::lines(["a", "b"])),
+            "Input::Lines(..)"
+        );
+    }
+
+    #[cfg(unix)]
+    mod unix {
+        use super::*;
+
+        fn cat(input: Input) -> Vec<u8> {
+            let mut cmd = Command::new("cat");
+            cmd.stdout(Stdio::piped());
+            let output = output_with_input(&mut cmd, input).unwrap();
+            assert!(output.status.success(), "cat failed: {}", output.status);
+            output.stdout
+        }
+
+        #[test]
+        fn test_bytes() {
+            assert_eq!(cat(Input::from(&b"\x00\xFFdata"[..])), b"\x00\xFFdata");
+        }
+
+        #[test]
+        fn test_reader() {
+            let data = b"line one\nline two\n";
+            assert_eq!(cat(Input::reader(io::Cursor::new(data))), data);
+        }
+
+        #[test]
+        fn test_lines() {
+            let lines = (1..=3).map(|i| format!("line {i}"));
+            assert_eq!(cat(Input::lines(lines)), b"line 1\nline 2\nline 3\n");
+        }
+
+        #[test]
+        fn test_large_input_and_output() {
+            // Far more than a pipe buffer in each direction
+            let data = "0123456789abcdef".repeat(1 << 16);
+            assert_eq!(cat(Input::from(data.as_str())), data.as_bytes());
+        }
+
+        #[test]
+        fn test_unread_input() {
+            let mut cmd = Command::new("true");
+            let data = vec![b'x'; 1 << 20];
+            let output = output_with_input(&mut cmd, Input::from(data)).unwrap();
+            assert!(output.status.success(), "true failed: {}", output.status);
+        }
+    }
+}
@@ -1,7 +1,19 @@
+mod capture;
+mod failure;
+mod input;
+mod lines;
+mod pipeline;
+pub use crate::capture::{CapturedOutput, Readcmd};
+use crate::failure::cmdline;
+pub use crate::failure::{CommandFailure, DetailedFailure};
+pub use crate::input::Input;
+use crate::input::output_with_input;
+pub use crate::lines::CommandLines;
+pub use crate::pipeline::{Pipeline, PipelineOutput};
 use bstr::ByteVec; // into_string_lossy()
 use rswodlib::strings::trim_string::trim_string;
 use std::ffi::OsStr;
-use std::process::{Command, ExitStatus, Stdio};
+use std::process::{Command, Output, Stdio};
 use std::str;
 use thiserror::Error;
 
@@ -10,75 +22,217 @@ where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
-    let rc = Command::new(arg0)
-        .args(args)
-        .status()
-        .map_err(RuncmdError::Startup)?;
+    let mut cmd = Command::new(arg0);
+    cmd.args(args);
+    let rc = cmd.status().map_err(|source| RuncmdError::Startup {
+        cmdline: cmdline(&cmd),
+        source,
+    })?;
     if rc.success() {
         Ok(())
     } else {
-        Err(RuncmdError::Exit(rc))
+        Err(RuncmdError::Exit(CommandFailure::new(&cmd, rc)))
     }
 }
 
-pub fn readcmd<I, S>(arg0: &str, args: I) -> Result<String, ReadcmdError>
+/// Like [`runcmd()`], but `input` is written to the command's stdin
+pub fn runcmd_with_input<I, S>(arg0: &str, args: I, input: Input) -> Result<(), RuncmdError>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
-    let out = Command::new(arg0)
-        .args(args)
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(ReadcmdError::Startup)?;
+    let mut cmd = Command::new(arg0);
+    cmd.args(args);
+    let out = output_with_input(&mut cmd, input)?;
     if out.status.success() {
-        match String::from_utf8(out.stdout) {
-            Ok(mut s) => {
-                trim_string(&mut s);
-                Ok(s)
-            }
-            Err(e) => Err(ReadcmdError::Decode(e.utf8_error())),
-        }
+        Ok(())
     } else {
-        Err(ReadcmdError::Exit(out.status))
+        Err(RuncmdError::Exit(CommandFailure::new(&cmd, out.status)))
     }
 }
 
+pub fn readcmd<I, S>(arg0: &str, args: I) -> Result<String, ReadcmdError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = Command::new(arg0);
+    cmd.args(args).stderr(Stdio::inherit());
+    let out = cmd.output().map_err(|source| ReadcmdError::Startup {
+        cmdline: cmdline(&cmd),
+        source,
+    })?;
+    decode_output(&cmd, out)
+}
+
+/// Like [`readcmd()`], but `input` is written to the command's stdin
+pub fn readcmd_with_input<I, S>(arg0: &str, args: I, input: Input) -> Result<String, ReadcmdError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = Command::new(arg0);
+    cmd.args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let out = output_with_input(&mut cmd, input)?;
+    decode_output(&cmd, out)
+}
+
 pub fn readcmd_lossy<I, S>(arg0: &str, args: I) -> Result<String, RuncmdError>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
-    let out = Command::new(arg0)
-        .args(args)
-        .stderr(Stdio::inherit())
-        .output()
-        .map_err(RuncmdError::Startup)?;
+    let mut cmd = Command::new(arg0);
+    cmd.args(args).stderr(Stdio::inherit());
+    let out = cmd.output().map_err(|source| RuncmdError::Startup {
+        cmdline: cmdline(&cmd),
+        source,
+    })?;
+    decode_output_lossy(&cmd, out)
+}
+
+/// Like [`readcmd_lossy()`], but `input` is written to the command's stdin
+pub fn readcmd_lossy_with_input<I, S>(
+    arg0: &str,
+    args: I,
+    input: Input,
+) -> Result<String, RuncmdError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = Command::new(arg0);
+    cmd.args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let out = output_with_input(&mut cmd, input)?;
+    decode_output_lossy(&cmd, out)
+}
+
+/// Start running a command and return an iterator over the lines of its
+/// stdout as they are produced.  The command's exit status is checked once
+/// its output is exhausted; see [`CommandLines`] for details.
+pub fn readcmd_lines<I, S>(arg0: &str, args: I) -> Result<CommandLines, ReadcmdError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = Command::new(arg0);
+    cmd.args(args).stderr(Stdio::inherit());
+    CommandLines::spawn(cmd)
+}
+
+/// Check the exit status of `out` and return its stdout decoded as UTF-8
+/// and trimmed
+fn decode_output(cmd: &Command, out: Output) -> Result<String, ReadcmdError> {
     if out.status.success() {
-        let mut s = <Vec<u8>>::into_string_lossy(out.stdout);
-        trim_string(&mut s);
-        Ok(s)
+        decode_stdout(out.stdout)
     } else {
-        Err(RuncmdError::Exit(out.status))
+        Err(ReadcmdError::Exit(CommandFailure::new(cmd, out.status)))
     }
 }
 
+/// Check the exit status of `out` and return its stdout decoded lossily and
+/// trimmed
+fn decode_output_lossy(cmd: &Command, out: Output) -> Result<String, RuncmdError> {
+    if out.status.success() {
+        Ok(decode_stdout_lossy(out.stdout))
+    } else {
+        Err(RuncmdError::Exit(CommandFailure::new(cmd, out.status)))
+    }
+}
+
+fn decode_stdout(stdout: Vec<u8>) -> Result<String, ReadcmdError> {
+    match String::from_utf8(stdout) {
+        Ok(mut s) => {
+            trim_string(&mut s);
+            Ok(s)
+        }
+        Err(e) => Err(ReadcmdError::Decode(e.utf8_error())),
+    }
+}
+
+fn decode_stdout_lossy(stdout: Vec<u8>) -> String {
+    let mut s = <Vec<u8>>::into_string_lossy(stdout);
+    trim_string(&mut s);
+    s
+}
+
 #[derive(Debug, Error)]
 pub enum RuncmdError {
-    #[error("failed to execute command: {0}")]
-    Startup(#[source] std::io::Error),
-    #[error("command exited unsuccessfully: {0}")]
-    Exit(ExitStatus),
+    #[error("failed to execute `{cmdline}`: {source}")]
+    Startup {
+        cmdline: String,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Exit(CommandFailure),
+    #[error("error reading output from command: {0}")]
+    Read(#[source] std::io::Error),
+    #[error("error writing to command's stdin: {0}")]
+    Write(#[source] std::io::Error),
+    #[error("error waiting for command to terminate: {0}")]
+    Wait(#[source] std::io::Error),
 }
 
 #[derive(Debug, Error)]
 pub enum ReadcmdError {
-    #[error("failed to execute command: {0}")]
-    Startup(#[source] std::io::Error),
-    #[error("command exited unsuccessfully: {0}")]
-    Exit(ExitStatus),
+    #[error("failed to execute `{cmdline}`: {source}")]
+    Startup {
+        cmdline: String,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Exit(CommandFailure),
+    /// The command was killed for exceeding its timeout.  The
+    /// `CommandFailure`'s `timeout` field is always set for this variant.
+    #[error(transparent)]
+    Timeout(CommandFailure),
     #[error("could not decode command output: {0}")]
     Decode(#[source] str::Utf8Error),
+    #[error("error reading output from command: {0}")]
+    Read(#[source] std::io::Error),
+    #[error("error writing to command's stdin: {0}")]
+    Write(#[source] std::io::Error),
+    #[error("error waiting for command to terminate: {0}")]
+    Wait(#[source] std::io::Error),
+}
+
+/// An error that occurred while running a command, before its exit status
+/// and output were examined
+#[derive(Debug)]
+enum RunError {
+    Startup {
+        cmdline: String,
+        source: std::io::Error,
+    },
+    Read(std::io::Error),
+    Write(std::io::Error),
+    Wait(std::io::Error),
+}
+
+impl From<RunError> for RuncmdError {
+    fn from(e: RunError) -> RuncmdError {
+        match e {
+            RunError::Startup { cmdline, source } => RuncmdError::Startup { cmdline, source },
+            RunError::Read(e) => RuncmdError::Read(e),
+            RunError::Write(e) => RuncmdError::Write(e),
+            RunError::Wait(e) => RuncmdError::Wait(e),
+        }
+    }
+}
+
+impl From<RunError> for ReadcmdError {
+    fn from(e: RunError) -> ReadcmdError {
+        match e {
+            RunError::Startup { cmdline, source } => ReadcmdError::Startup { cmdline, source },
+            RunError::Read(e) => ReadcmdError::Read(e),
+            RunError::Write(e) => ReadcmdError::Write(e),
+            RunError::Wait(e) => ReadcmdError::Wait(e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -88,15 +242,16 @@ mod tests {
     #[test]
     fn runcmd_startup_failure() {
         let r = runcmd::<[&str; 0], _>("this-command-does-not-exist", []);
-        let Err(RuncmdError::Startup(_)) = r else {
+        let Err(RuncmdError::Startup { cmdline, .. }) = r else {
             panic!("Command did not fail to start: {r:?}");
         };
+        assert_eq!(cmdline, "this-command-does-not-exist");
     }
 
     #[test]
     fn readcmd_startup_failure() {
         let r = readcmd("nonexistent-echo", ["This", "is", "test", "text."]);
-        let Err(ReadcmdError::Startup(_)) = r else {
+        let Err(ReadcmdError::Startup { .. }) = r else {
             panic!("Command did not fail to start: {r:?}");
         };
     }
@@ -104,7 +259,7 @@ mod tests {
     #[test]
     fn readcmd_lossy_startup_failure() {
         let r = readcmd_lossy("nonexistent-echo", ["This", "is", "test", "text."]);
-        let Err(RuncmdError::Startup(_)) = r else {
+        let Err(RuncmdError::Startup { .. }) = r else {
             panic!("Command did not fail to start: {r:?}");
         };
     }
@@ -118,10 +273,16 @@ mod tests {
         #[test]
         fn runcmd_bad_exit() {
             let r = runcmd("sh", ["-c", "exit 42"]);
-            let Err(RuncmdError::Exit(rc)) = r else {
+            let Err(RuncmdError::Exit(failure)) = r else {
                 panic!("Command did not exit nonzero: {r:?}");
             };
-            assert_eq!(rc.code(), Some(42));
+            assert_eq!(failure.status.code(), Some(42));
+            assert_eq!(failure.cmdline, "sh -c 'exit 42'");
+            assert_eq!(failure.cwd, None);
+            assert_eq!(
+                failure.to_string(),
+                "command `sh -c 'exit 42'` exited with status 42"
+            );
         }
 
         #[test]
@@ -159,10 +320,51 @@ mod tests {
         #[test]
         fn readcmd_bad_exit() {
             let r = readcmd("sh", ["-c", r"printf 'This will be discarded.\n'; exit 23"]);
-            let Err(ReadcmdError::Exit(rc)) = r else {
+            let Err(ReadcmdError::Exit(failure)) = r else {
+                panic!("Command did not exit nonzero: {r:?}");
+            };
+            assert_eq!(failure.status.code(), Some(23));
+            assert_eq!(failure.stderr_tail, None);
+        }
+
+        #[test]
+        fn test_readcmd_with_input() {
+            let out = readcmd_with_input("tr", ["a-z", "A-Z"], Input::from("hello\n")).unwrap();
+            assert_eq!(out, "HELLO");
+        }
+
+        #[test]
+        fn readcmd_with_input_non_utf8() {
+            let r = readcmd_with_input::<[&str; 0], _>("cat", [], Input::from(&b"\x80\n"[..]));
+            let Err(ReadcmdError::Decode(_)) = r else {
+                panic!("Command did not fail on decoding output: {r:?}");
+            };
+        }
+
+        #[test]
+        fn readcmd_with_input_bad_exit() {
+            let r = readcmd_with_input("sh", ["-c", "cat; exit 5"], Input::from("ignored"));
+            let Err(ReadcmdError::Exit(failure)) = r else {
+                panic!("Command did not exit nonzero: {r:?}");
+            };
+            assert_eq!(failure.status.code(), Some(5));
+        }
+
+        #[test]
+        fn test_readcmd_lossy_with_input() {
+            let input = Input::reader(std::io::Cursor::new(b"caf\xE9\n".to_vec()));
+            let out = readcmd_lossy_with_input::<[&str; 0], _>("cat", [], input).unwrap();
+            assert_eq!(out, "caf\u{FFFD}");
+        }
+
+        #[test]
+        fn test_runcmd_with_input() {
+            runcmd_with_input("grep", ["-q", "needle"], Input::lines(["hay", "needle"])).unwrap();
+            let r = runcmd_with_input("grep", ["-q", "needle"], Input::lines(["hay", "stack"]));
+            let Err(RuncmdError::Exit(failure)) = r else {
                 panic!("Command did not exit nonzero: {r:?}");
             };
-            assert_eq!(rc.code(), Some(23));
+            assert_eq!(failure.status.code(), Some(1));
         }
 
         #[test]
@@ -180,10 +382,10 @@ mod tests {
         #[test]
         fn readcmd_lossy_bad_exit() {
             let r = readcmd_lossy("sh", ["-c", r"printf 'This will be discarded.\n'; exit 23"]);
-            let Err(RuncmdError::Exit(rc)) = r else {
+            let Err(RuncmdError::Exit(failure)) = r else {
                 panic!("Command did not exit nonzero: {r:?}");
             };
-            assert_eq!(rc.code(), Some(23));
+            assert_eq!(failure.status.code(), Some(23));
         }
     }
 }
@@ -0,0 +1,370 @@
+use crate::ReadcmdError;
+use crate::failure::{CommandFailure, cmdline};
+use rswodlib::strings::lines::lines_keepends::lines_keepends;
+use rswodlib::utf8::str_decoder::Utf8StrDecoder;
+use std::io::{ErrorKind, Read};
+use std::iter::FusedIterator;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+const CHUNK_SIZE: usize = 8192;
+
+/// An iterator over the lines of a running command's stdout, as returned by
+/// [`readcmd_lines()`](crate::readcmd_lines) and [`CommandLines::spawn()`]
+///
+/// Lines are yielded as soon as they are read, with their terminators
+/// retained.  As with
+/// [`lines_keepends()`](rswodlib::strings::lines::lines_keepends::lines_keepends),
+/// LF, CR LF, and a lone CR are all treated as line terminators, and the
+/// final line need not have one.
+///
+/// Once the command's stdout is exhausted, the iterator waits for the
+/// command to exit and checks its exit status.  If the command failed, if
+/// its output was not valid UTF-8, or if an I/O error occurred, the error is
+/// yielded as the iterator's final item.  If the output was not valid UTF-8
+/// (including if it ended partway through a character), any complete lines
+/// preceding the invalid bytes are yielded first, while any incomplete line
+/// immediately before them is discarded.  If the invalid bytes were not at
+/// the end of the output, the command is then killed, as is also done after
+/// an I/O error.
+///
+/// If the iterator is dropped before it is exhausted, the command's stdout
+/// is closed without being read further, and the command is killed (if it's
+/// still running) and reaped, so that dropping the iterator never blocks on
+/// a command that runs indefinitely, like `tail -f`.  The exit status is
+/// discarded in this case.  Use [`CommandLines::finish()`] to instead read
+/// the rest of the output, wait for the command to exit, and check its exit
+/// status.
+#[derive(Debug)]
+pub struct CommandLines {
+    cmd: Command,
+    child: Option<Child>,
+    stdout: Option<ChildStdout>,
+    decoder: Utf8StrDecoder,
+    buffer: String,
+    // A decoding error to yield once the complete lines in `buffer` have been
+    // yielded
+    error: Option<ReadcmdError>,
+}
+
+impl CommandLines {
+    /// Start running `cmd` and return an iterator over the lines of its
+    /// stdout.  The command's stdout will be overridden.
+    pub fn spawn(mut cmd: Command) -> Result<CommandLines, ReadcmdError> {
+        let mut child =
+            cmd.stdout(Stdio::piped())
+                .spawn()
+                .map_err(|source| ReadcmdError::Startup {
+                    cmdline: cmdline(&cmd),
+                    source,
+                })?;
+        let stdout = child.stdout.take();
+        Ok(CommandLines {
+            cmd,
+            child: Some(child),
+            stdout,
+            decoder: Utf8StrDecoder::new(),
+            buffer: String::new(),
+            error: None,
+        })
+    }
+
+    /// Read & discard the rest of the command's output, wait for the command
+    /// to exit, and check its exit status
+    pub fn finish(mut self) -> Result<(), ReadcmdError> {
+        for r in &mut self {
+            r?;
+        }
+        Ok(())
+    }
+
+    /// Remove & return the first line in the buffer if it is known to be
+    /// complete.  If `eof` is true, any remaining text is treated as a
+    /// complete line.
+    fn take_line(&mut self, eof: bool) -> Option<String> {
+        let line = lines_keepends(&self.buffer).next()?;
+        // A trailing CR may turn out to be the start of a CR LF sequence, so
+        // the line isn't complete until we see what comes after it, unless
+        // what comes after it is the invalid input that caused a pending
+        // error.
+        let complete = line.len() < self.buffer.len()
+            || line.ends_with('\n')
+            || (line.ends_with('\r') && self.error.is_some())
+            || eof;
+        if !complete {
+            return None;
+        }
+        let rest = self.buffer.split_off(line.len());
+        Some(std::mem::replace(&mut self.buffer, rest))
+    }
+
+    /// Read the next chunk of output into the buffer.  Returns `Ok(false)`
+    /// on end of file.
+    fn fill_buffer(&mut self) -> Result<bool, ReadcmdError> {
+        let Some(stdout) = self.stdout.as_mut() else {
+            return Ok(false);
+        };
+        let mut chunk = [0u8; CHUNK_SIZE];
+        let n = loop {
+            match stdout.read(&mut chunk) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(ReadcmdError::Read(e)),
+            }
+        };
+        if n == 0 {
+            self.stdout = None;
+            // Check for a truncated character now so that the text before it
+            // is handled the same as text before invalid bytes.
+            if let Err(e) = std::mem::take(&mut self.decoder).finish() {
+                self.error = Some(ReadcmdError::Decode(e.utf8_error()));
+                return Ok(true);
+            }
+            return Ok(false);
+        }
+        match self.decoder.decode(&chunk[..n]) {
+            Ok(s) => self.buffer.push_str(&s),
+            Err(e) => {
+                // Keep the text before the invalid bytes so that any complete
+                // lines in it can be yielded before the error.
+                let err = e.utf8_error();
+                let mut bytes = e.into_bytes();
+                bytes.truncate(err.valid_up_to());
+                if let Ok(s) = String::from_utf8(bytes) {
+                    self.buffer.push_str(&s);
+                }
+                self.error = Some(ReadcmdError::Decode(err));
+                self.kill();
+            }
+        }
+        Ok(true)
+    }
+
+    /// Wait for the command to exit after its output has been exhausted and
+    /// check its exit status
+    fn wait(&mut self) -> Result<(), ReadcmdError> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+        let status = child.wait().map_err(ReadcmdError::Wait)?;
+        if !status.success() {
+            return Err(ReadcmdError::Exit(CommandFailure::new(&self.cmd, status)));
+        }
+        Ok(())
+    }
+
+    /// Stop reading output, then kill the command (if it's still running)
+    /// and reap it
+    fn kill(&mut self) {
+        self.stdout = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Iterator for CommandLines {
+    type Item = Result<String, ReadcmdError>;
+
+    fn next(&mut self) -> Option<Result<String, ReadcmdError>> {
+        loop {
+            // When a decoding error is pending, only complete lines are
+            // yielded before it; any incomplete text is discarded.
+            let eof = self.stdout.is_none() && self.error.is_none();
+            if let Some(line) = self.take_line(eof) {
+                return Some(Ok(line));
+            }
+            if let Some(e) = self.error.take() {
+                self.buffer.clear();
+                // If the error was found at the end of the output, the
+                // command hasn't been killed, and a failed exit status takes
+                // precedence.
+                return Some(Err(self.wait().err().unwrap_or(e)));
+            }
+            match self.fill_buffer() {
+                Ok(true) => (),
+                Ok(false) => {
+                    if let Some(line) = self.take_line(true) {
+                        return Some(Ok(line));
+                    }
+                    return self.wait().err().map(Err);
+                }
+                Err(e) => {
+                    self.kill();
+                    self.buffer.clear();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl FusedIterator for CommandLines {}
+
+impl Drop for CommandLines {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    mod unix {
+        use super::*;
+        use crate::readcmd_lines;
+        use std::time::{Duration, Instant};
+
+        fn collect(lines: CommandLines) -> (Vec<String>, Option<ReadcmdError>) {
+            let mut out = Vec::new();
+            for r in lines {
+                match r {
+                    Ok(line) => out.push(line),
+                    Err(e) => return (out, Some(e)),
+                }
+            }
+            (out, None)
+        }
+
+        #[test]
+        fn test_lines() {
+            let lines = readcmd_lines("printf", [r"foo\r\nbar\n\rbaz\n"]).unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["foo\r\n", "bar\n", "\r", "baz\n"]);
+            assert!(err.is_none(), "unexpected error: {err:?}");
+        }
+
+        #[test]
+        fn test_no_final_terminator() {
+            let lines = readcmd_lines("printf", [r"foo\nbar\r"]).unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["foo\n", "bar\r"]);
+            assert!(err.is_none(), "unexpected error: {err:?}");
+        }
+
+        #[test]
+        fn test_split_across_reads() {
+            // Both the CR LF and the multibyte character are split between
+            // writes
+            let script =
+                r"printf 'foo\r'; sleep 0.1; printf '\nb\303'; sleep 0.1; printf '\251r\n'";
+            let lines = readcmd_lines("sh", ["-c", script]).unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["foo\r\n", "b\u{E9}r\n"]);
+            assert!(err.is_none(), "unexpected error: {err:?}");
+        }
+
+        #[test]
+        fn test_streaming() {
+            // The command doesn't print its second line until the file `go`
+            // is created, which only happens after the first line is read.
+            let tmpdir = assert_fs::TempDir::new().unwrap();
+            let go = tmpdir.path().join("go");
+            let script = r#"echo first; while [ ! -e "$1" ]; do sleep 0.05; done; echo second"#;
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", script, "sh"]).arg(&go);
+            let mut lines = CommandLines::spawn(cmd).unwrap();
+            assert_eq!(lines.next().unwrap().unwrap(), "first\n");
+            std::fs::write(&go, "").unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["second\n"]);
+            assert!(err.is_none(), "unexpected error: {err:?}");
+        }
+
+        #[test]
+        fn test_drop_endless_output() {
+            let lines = readcmd_lines("yes", Vec::<String>::new()).unwrap();
+            let first = lines.take(3).collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(first, ["y\n", "y\n", "y\n"]);
+        }
+
+        #[test]
+        fn test_drop_running() {
+            let start = Instant::now();
+            // The command would block forever if it weren't killed, as it
+            // doesn't write anything after its first line.
+            let mut lines = readcmd_lines("sh", ["-c", "echo first; sleep 30"]).unwrap();
+            assert_eq!(lines.next().unwrap().unwrap(), "first\n");
+            drop(lines);
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "Dropping the iterator took too long"
+            );
+        }
+
+        #[test]
+        fn test_bad_exit() {
+            let lines = readcmd_lines("sh", ["-c", "echo foo; exit 3"]).unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["foo\n"]);
+            let Some(ReadcmdError::Exit(failure)) = err else {
+                panic!("Command did not exit nonzero: {err:?}");
+            };
+            assert_eq!(failure.status.code(), Some(3));
+            assert_eq!(failure.cmdline, "sh -c 'echo foo; exit 3'");
+        }
+
+        #[test]
+        fn test_non_utf8() {
+            let mut lines = readcmd_lines("printf", [r"ok\nbad \200\n"]).unwrap();
+            assert_eq!(lines.next().unwrap().unwrap(), "ok\n");
+            let r = lines.next();
+            assert!(matches!(r, Some(Err(ReadcmdError::Decode(_)))), "{r:?}");
+            assert!(lines.next().is_none());
+        }
+
+        #[test]
+        fn test_truncated_utf8() {
+            let mut lines = readcmd_lines("printf", [r"ok\ncaf\303"]).unwrap();
+            assert_eq!(lines.next().unwrap().unwrap(), "ok\n");
+            let r = lines.next();
+            assert!(matches!(r, Some(Err(ReadcmdError::Decode(_)))), "{r:?}");
+            assert!(lines.next().is_none());
+        }
+
+        #[test]
+        fn test_non_utf8_after_cr() {
+            let lines = readcmd_lines("printf", [r"ok\rpartial\r\200"]).unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["ok\r", "partial\r"]);
+            assert!(matches!(err, Some(ReadcmdError::Decode(_))), "{err:?}");
+        }
+
+        #[test]
+        fn test_truncated_utf8_bad_exit() {
+            let lines = readcmd_lines("sh", ["-c", r"printf 'ok\ncaf\303'; exit 2"]).unwrap();
+            let (lines, err) = collect(lines);
+            assert_eq!(lines, ["ok\n"]);
+            let Some(ReadcmdError::Exit(failure)) = err else {
+                panic!("Command did not exit nonzero: {err:?}");
+            };
+            assert_eq!(failure.status.code(), Some(2));
+        }
+
+        #[test]
+        fn test_finish() {
+            let mut lines = readcmd_lines("sh", ["-c", "echo one; echo two; exit 7"]).unwrap();
+            assert_eq!(lines.next().unwrap().unwrap(), "one\n");
+            let r = lines.finish();
+            let Err(ReadcmdError::Exit(failure)) = r else {
+                panic!("Command did not exit nonzero: {r:?}");
+            };
+            assert_eq!(failure.status.code(), Some(7));
+        }
+
+        #[test]
+        fn test_spawn_command() {
+            let tmpdir = assert_fs::TempDir::new().unwrap();
+            let mut cmd = Command::new("pwd");
+            cmd.current_dir(tmpdir.path());
+            let lines = CommandLines::spawn(cmd)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(lines.len(), 1);
+            assert!(lines[0].ends_with('\n'));
+        }
+    }
+}
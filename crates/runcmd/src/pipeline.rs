@@ -0,0 +1,353 @@
+use crate::failure::{CommandFailure, cmdline};
+use crate::input::{Input, join_writer, spawn_writer};
+use crate::{ReadcmdError, RunError, RuncmdError, decode_stdout, decode_stdout_lossy};
+use std::ffi::OsStr;
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+/// A builder for running a pipeline of commands (as in `a | b | c`) without
+/// involving a shell.  The stdout of each command is connected to the stdin
+/// of the next, while the stderr of each command is inherited from the
+/// current process.
+///
+/// By default, the pipeline has "pipefail" semantics: it is considered to
+/// have failed if any command in it exits unsuccessfully, and the rightmost
+/// such command is reported in errors.  With pipefail disabled, only the
+/// exit status of the last command matters.  Note that a command that is
+/// still writing when a later command exits is usually killed by `SIGPIPE`,
+/// which counts as a failure under pipefail.
+///
+/// # Example
+///
+/// ```no_run
+/// # use rswodlib_runcmd::Pipeline;
+/// let authors = Pipeline::new()
+///     .pipe("git", ["log", "--format=%an"])
+///     .pipe::<[&str; 0], _>("sort", [])
+///     .pipe("uniq", ["-c"])
+///     .read()?;
+/// # Ok::<(), rswodlib_runcmd::ReadcmdError>(())
+/// ```
+#[derive(Debug)]
+pub struct Pipeline {
+    stages: Vec<Command>,
+    pipefail: bool,
+    input: Option<Input>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Pipeline {
+            stages: Vec::new(),
+            pipefail: true,
+            input: None,
+        }
+    }
+
+    /// Append a command with the given program & arguments to the pipeline
+    pub fn pipe<I, S>(&mut self, arg0: &str, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut cmd = Command::new(arg0);
+        cmd.args(args);
+        self.pipe_command(cmd)
+    }
+
+    /// Append a preconfigured `Command` to the pipeline.  The command's
+    /// stdin and stdout will be overridden, except for the stdin of the
+    /// first command if no input is set via [`Pipeline::stdin()`].
+    pub fn pipe_command(&mut self, cmd: Command) -> &mut Self {
+        self.stages.push(cmd);
+        self
+    }
+
+    /// Set whether the pipeline fails if any command in it fails (`true`,
+    /// the default) or only if the last command fails (`false`)
+    pub fn pipefail(&mut self, pipefail: bool) -> &mut Self {
+        self.pipefail = pipefail;
+        self
+    }
+
+    /// Write `input` to the stdin of the first command.  As the input is
+    /// consumed when the pipeline is run, it only applies to the next run.
+    pub fn stdin(&mut self, input: Input) -> &mut Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Run the pipeline and return the exit status of every command along
+    /// with the stdout of the last command.  The exit statuses are not
+    /// checked; use [`PipelineOutput::failed_stage()`] to do so.
+    pub fn output(&mut self) -> Result<PipelineOutput, ReadcmdError> {
+        Ok(self.run()?)
+    }
+
+    /// Run the pipeline and return the stdout of the last command, decoded
+    /// as UTF-8 and with leading & trailing whitespace removed
+    pub fn read(&mut self) -> Result<String, ReadcmdError> {
+        let out = self.run()?;
+        if let Some(failure) = self.failure(&out) {
+            return Err(ReadcmdError::Exit(failure));
+        }
+        decode_stdout(out.stdout)
+    }
+
+    /// Like [`Pipeline::read()`], but invalid UTF-8 in the output is
+    /// replaced with U+FFFD REPLACEMENT CHARACTER
+    pub fn read_lossy(&mut self) -> Result<String, RuncmdError> {
+        let out = self.run()?;
+        if let Some(failure) = self.failure(&out) {
+            return Err(RuncmdError::Exit(failure));
+        }
+        Ok(decode_stdout_lossy(out.stdout))
+    }
+
+    /// Run the pipeline without checking the exit statuses
+    fn run(&mut self) -> Result<PipelineOutput, RunError> {
+        let input = self.input.take();
+        let n = self.stages.len();
+        let mut children: Vec<Child> = Vec::with_capacity(n);
+        for cmd in &mut self.stages {
+            let piped = if let Some(prev) = children.last_mut().and_then(|c| c.stdout.take()) {
+                cmd.stdin(prev);
+                true
+            } else if input.is_some() {
+                cmd.stdin(Stdio::piped());
+                true
+            } else {
+                false
+            };
+            cmd.stdout(Stdio::piped());
+            let spawned = cmd.spawn();
+            if piped {
+                // Release the `Command`'s handle on the previous command's
+                // stdout so that the previous command receives `SIGPIPE`
+                // once this one exits.
+                cmd.stdin(Stdio::inherit());
+            }
+            match spawned {
+                Ok(child) => children.push(child),
+                Err(source) => {
+                    for mut child in children {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                    }
+                    return Err(RunError::Startup {
+                        cmdline: cmdline(cmd),
+                        source,
+                    });
+                }
+            }
+        }
+        let writer = input
+            .zip(children.first_mut())
+            .map(|(input, first)| spawn_writer(first, input));
+        let mut stdout = Vec::new();
+        let read = match children.last_mut().and_then(|c| c.stdout.take()) {
+            Some(mut out) => out.read_to_end(&mut stdout).map(|_| ()),
+            None => Ok(()),
+        };
+        let mut statuses = Vec::with_capacity(n);
+        let mut wait_error = None;
+        for mut child in children {
+            match child.wait() {
+                Ok(status) => statuses.push(status),
+                Err(e) => {
+                    wait_error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(writer) = writer {
+            join_writer(writer).map_err(RunError::Write)?;
+        }
+        read.map_err(RunError::Read)?;
+        if let Some(e) = wait_error {
+            return Err(RunError::Wait(e));
+        }
+        Ok(PipelineOutput {
+            statuses,
+            stdout,
+            pipefail: self.pipefail,
+        })
+    }
+
+    fn failure(&self, out: &PipelineOutput) -> Option<CommandFailure> {
+        let i = out.failed_stage()?;
+        Some(CommandFailure::new(&self.stages[i], out.statuses[i]))
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline::new()
+    }
+}
+
+/// The output of a [`Pipeline`], as returned by [`Pipeline::output()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PipelineOutput {
+    /// The exit status of each command in the pipeline, in order
+    pub statuses: Vec<ExitStatus>,
+
+    /// The stdout of the last command in the pipeline
+    pub stdout: Vec<u8>,
+
+    pipefail: bool,
+}
+
+impl PipelineOutput {
+    /// Returns the index of the command that caused the pipeline to fail, if
+    /// any, according to the pipeline's pipefail setting
+    pub fn failed_stage(&self) -> Option<usize> {
+        if self.pipefail {
+            self.statuses.iter().rposition(|st| !st.success())
+        } else {
+            let last = self.statuses.len().checked_sub(1)?;
+            (!self.statuses[last].success()).then_some(last)
+        }
+    }
+
+    /// Returns `true` if the pipeline succeeded according to its pipefail
+    /// setting
+    pub fn success(&self) -> bool {
+        self.failed_stage().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    mod unix {
+        use super::*;
+
+        #[test]
+        fn test_read() {
+            let out = Pipeline::new()
+                .pipe("printf", [r"b\na\nc\na\n"])
+                .pipe::<[&str; 0], _>("sort", [])
+                .pipe("uniq", ["-c"])
+                .read()
+                .unwrap();
+            let lines = out
+                .lines()
+                .map(|ln| ln.split_whitespace().collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            assert_eq!(lines, [["2", "a"], ["1", "b"], ["1", "c"]]);
+        }
+
+        #[test]
+        fn test_single_stage() {
+            let out = Pipeline::new().pipe("echo", ["hello"]).read().unwrap();
+            assert_eq!(out, "hello");
+        }
+
+        #[test]
+        fn test_empty() {
+            let out = Pipeline::new().output().unwrap();
+            assert!(out.statuses.is_empty());
+            assert!(out.stdout.is_empty());
+            assert!(out.success());
+        }
+
+        #[test]
+        fn test_stdin() {
+            let out = Pipeline::new()
+                .stdin(Input::lines(["one", "two", "three"]))
+                .pipe("grep", ["o"])
+                .pipe("tr", ["a-z", "A-Z"])
+                .read()
+                .unwrap();
+            assert_eq!(out, "ONE\nTWO");
+        }
+
+        #[test]
+        fn test_pipefail() {
+            let mut pipeline = Pipeline::new();
+            pipeline
+                .pipe("sh", ["-c", "echo foo; exit 3"])
+                .pipe::<[&str; 0], _>("cat", []);
+            let out = pipeline.output().unwrap();
+            assert_eq!(out.stdout, b"foo\n");
+            assert_eq!(out.statuses[0].code(), Some(3));
+            assert!(out.statuses[1].success());
+            assert_eq!(out.failed_stage(), Some(0));
+            let r = pipeline.read();
+            let Err(ReadcmdError::Exit(failure)) = r else {
+                panic!("Pipeline did not fail: {r:?}");
+            };
+            assert_eq!(failure.cmdline, "sh -c 'echo foo; exit 3'");
+            assert_eq!(failure.status.code(), Some(3));
+        }
+
+        #[test]
+        fn test_no_pipefail() {
+            let out = Pipeline::new()
+                .pipe("sh", ["-c", "echo foo; exit 3"])
+                .pipe::<[&str; 0], _>("cat", [])
+                .pipefail(false)
+                .read()
+                .unwrap();
+            assert_eq!(out, "foo");
+            let r = Pipeline::new()
+                .pipe("echo", ["foo"])
+                .pipe("sh", ["-c", "cat; exit 4"])
+                .pipefail(false)
+                .read_lossy();
+            let Err(RuncmdError::Exit(failure)) = r else {
+                panic!("Pipeline did not fail: {r:?}");
+            };
+            assert_eq!(failure.status.code(), Some(4));
+        }
+
+        #[test]
+        fn test_rightmost_failure() {
+            let out = Pipeline::new()
+                .pipe("sh", ["-c", "exit 1"])
+                .pipe("sh", ["-c", "exit 2"])
+                .pipe::<[&str; 0], _>("true", [])
+                .output()
+                .unwrap();
+            assert_eq!(out.failed_stage(), Some(1));
+        }
+
+        #[test]
+        fn test_sigpipe() {
+            // `yes` only terminates once `head` exits and closes the pipe.
+            let out = Pipeline::new()
+                .pipe::<[&str; 0], _>("yes", [])
+                .pipe("head", ["-n", "2"])
+                .output()
+                .unwrap();
+            assert_eq!(out.stdout, b"y\ny\n");
+            assert_eq!(out.failed_stage(), Some(0));
+            let failure = CommandFailure::new(&Command::new("yes"), out.statuses[0]);
+            assert_eq!(failure.signal_name(), Some("SIGPIPE"));
+        }
+
+        #[test]
+        fn test_startup_failure() {
+            let r = Pipeline::new()
+                .pipe("sleep", ["30"])
+                .pipe::<[&str; 0], _>("this-command-does-not-exist", [])
+                .read();
+            let Err(ReadcmdError::Startup { cmdline, .. }) = r else {
+                panic!("Pipeline did not fail to start: {r:?}");
+            };
+            assert_eq!(cmdline, "this-command-does-not-exist");
+        }
+
+        #[test]
+        fn test_read_lossy() {
+            let out = Pipeline::new()
+                .pipe("printf", [r"caf\351\n"])
+                .pipe::<[&str; 0], _>("cat", [])
+                .read_lossy()
+                .unwrap();
+            assert_eq!(out, "caf\u{FFFD}");
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Mutex, PoisonError};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+/// An asynchronous counterpart to `rswodlib::jobstack::JobStack`: a
+/// synchronized stack of work items/jobs for use by a collection of
+/// concurrent tasks that are both producers and consumers of jobs.
+///
+/// After the stack is initialized with some starting jobs, each worker task
+/// calls `handle_job()` (likely in a loop) or `handle_many_jobs()` with an
+/// async function that takes a job and returns more jobs, which are pushed on
+/// top of the stack.  All work terminates when (a) all jobs have completed
+/// successfully, (b) a job has returned `Err`, or (c) `shutdown()` is called.
+///
+/// Each job is passed a `CancellationToken` that is cancelled when the stack
+/// is shut down, which long-running jobs can use to stop early.
+#[derive(Debug)]
+pub struct JobStack<T> {
+    data: Mutex<JobStackData<T>>,
+    notify: Notify,
+    token: CancellationToken,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct JobStackData<T> {
+    stack: Vec<T>,
+    jobs: usize,
+}
+
+impl<T> JobStack<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I) -> Self {
+        JobStack::with_token(items, CancellationToken::new())
+    }
+
+    /// Create a `JobStack` that is shut down when `token` is cancelled.
+    /// Shutting down the stack also cancels `token`.
+    pub fn with_token<I: IntoIterator<Item = T>>(items: I, token: CancellationToken) -> Self {
+        let stack = Vec::from_iter(items);
+        let jobs = stack.len();
+        JobStack {
+            data: Mutex::new(JobStackData { stack, jobs }),
+            notify: Notify::new(),
+            token,
+        }
+    }
+
+    /// Returns a clone of the stack's cancellation token
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Pop a job from the stack, wait for `f` to process it, and push the new
+    /// jobs it returns onto the stack.  Returns `Ok(false)` without calling
+    /// `f` if all jobs have completed or the stack has been shut down.
+    ///
+    /// If `f` returns `Err`, the stack is shut down and the error is
+    /// returned.
+    pub async fn handle_job<F, Fut, I, E>(&self, f: F) -> Result<bool, E>
+    where
+        F: FnOnce(T, CancellationToken) -> Fut,
+        Fut: Future<Output = Result<I, E>>,
+        I: IntoIterator<Item = T>,
+    {
+        let Some(value) = self.pop().await else {
+            return Ok(false);
+        };
+        let r = f(value, self.token()).await;
+        self.complete(r).map(|()| true)
+    }
+
+    /// Repeatedly call `f` on jobs from the stack and push the new jobs it
+    /// returns onto the stack until all jobs have completed, the stack is shut
+    /// down, or `f` returns `Err`, in which case the stack is shut down and
+    /// the error is returned.
+    pub async fn handle_many_jobs<F, Fut, I, E>(&self, mut f: F) -> Result<(), E>
+    where
+        F: FnMut(T, CancellationToken) -> Fut,
+        Fut: Future<Output = Result<I, E>>,
+        I: IntoIterator<Item = T>,
+    {
+        while let Some(value) = self.pop().await {
+            let r = f(value, self.token()).await;
+            self.complete(r)?;
+        }
+        Ok(())
+    }
+
+    /// Discard all pending jobs, cancel the stack's cancellation token, and
+    /// cause all current and future calls to `handle_job()` and
+    /// `handle_many_jobs()` to return once any jobs they're currently running
+    /// complete.
+    pub fn shutdown(&self) {
+        let mut data = self.lock();
+        let discarded = data.stack.len();
+        data.jobs -= discarded;
+        data.stack.clear();
+        self.token.cancel();
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    async fn pop(&self) -> Option<T> {
+        loop {
+            let mut notified = pin!(self.notify.notified());
+            // Register for notifications before checking the stack so that a
+            // notification sent in between is not missed.
+            notified.as_mut().enable();
+            {
+                let mut data = self.lock();
+                if data.jobs == 0 || self.is_shutdown() {
+                    return None;
+                }
+                if let value @ Some(_) = data.stack.pop() {
+                    return value;
+                }
+            }
+            tokio::select! {
+                () = notified => (),
+                () = self.token.cancelled() => (),
+            }
+        }
+    }
+
+    /// Record the completion of a job that returned `r`
+    fn complete<I: IntoIterator<Item = T>, E>(&self, r: Result<I, E>) -> Result<(), E> {
+        match r {
+            Ok(iter) => {
+                let mut data = self.lock();
+                if !self.is_shutdown() {
+                    let prelen = data.stack.len();
+                    data.stack.extend(iter);
+                    data.jobs += data.stack.len() - prelen;
+                }
+                data.jobs -= 1;
+                self.notify.notify_waiters();
+                Ok(())
+            }
+            Err(e) => {
+                self.lock().jobs -= 1;
+                self.shutdown();
+                Err(e)
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JobStackData<T>> {
+        // The data is never left in an inconsistent state, so we can ignore
+        // poisoning.
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Treat each job `n` as a node in a tree whose children are
+    /// `n * 3 + 1 ..= n * 3 + 3`, stopping at `limit`
+    fn tree_children(n: u32, limit: u32) -> Vec<u32> {
+        (n * 3 + 1..=n * 3 + 3).filter(|&c| c < limit).collect()
+    }
+
+    #[tokio::test]
+    async fn test_handle_many_jobs() {
+        let stack = JobStack::new([0]);
+        let seen = Mutex::new(Vec::new());
+        let seen_ref = &seen;
+        let worker = || async {
+            stack
+                .handle_many_jobs(|n, _| async move {
+                    tokio::task::yield_now().await;
+                    seen_ref.lock().unwrap().push(n);
+                    Ok::<_, ()>(tree_children(n, 500))
+                })
+                .await
+        };
+        let (r1, r2, r3) = tokio::join!(worker(), worker(), worker());
+        assert_eq!(r1, Ok(()));
+        assert_eq!(r2, Ok(()));
+        assert_eq!(r3, Ok(()));
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort_unstable();
+        assert_eq!(seen, (0..500).collect::<Vec<_>>());
+        assert!(!stack.is_shutdown());
+    }
+
+    #[tokio::test]
+    async fn test_handle_job() {
+        let stack = JobStack::new([1, 2]);
+        let r = stack
+            .handle_job(|n, _| async move { Ok::<_, ()>(vec![n * 10]) })
+            .await;
+        assert_eq!(r, Ok(true));
+        let mut seen = Vec::new();
+        while stack
+            .handle_job(|n, _| {
+                seen.push(n);
+                async { Ok::<_, ()>([]) }
+            })
+            .await
+            == Ok(true)
+        {}
+        assert_eq!(seen, [20, 1]);
+        assert_eq!(
+            stack
+                .handle_job(|n, _| async move { Ok::<_, ()>([n]) })
+                .await,
+            Ok(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_shuts_down() {
+        let stack = JobStack::new([0]);
+        let worker = || async {
+            stack
+                .handle_many_jobs(|n, token| async move {
+                    if n == 20 {
+                        Err(format!("job {n} failed"))
+                    } else if n == 5 {
+                        // Wait to be cancelled
+                        token.cancelled().await;
+                        Ok(vec![1000])
+                    } else {
+                        tokio::task::yield_now().await;
+                        Ok(tree_children(n, 10_000))
+                    }
+                })
+                .await
+        };
+        let rs = tokio::join!(worker(), worker(), worker());
+        let mut errors = [rs.0, rs.1, rs.2]
+            .into_iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>();
+        assert_eq!(errors.pop(), Some(String::from("job 20 failed")));
+        assert!(errors.is_empty(), "{errors:?}");
+        assert!(stack.is_shutdown());
+        assert_eq!(
+            stack
+                .handle_job(|n, _| async move { Ok::<_, ()>([n]) })
+                .await,
+            Ok(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_external_cancel() {
+        let token = CancellationToken::new();
+        let stack = JobStack::with_token([0], token.clone());
+        let worker = stack.handle_many_jobs(|n, token| async move {
+            tokio::select! {
+                () = token.cancelled() => (),
+                () = tokio::time::sleep(Duration::from_secs(10)) => (),
+            }
+            Ok::<_, ()>(tree_children(n, 100))
+        });
+        let canceller = async {
+            tokio::task::yield_now().await;
+            token.cancel();
+        };
+        let (r, ()) = tokio::join!(worker, canceller);
+        assert_eq!(r, Ok(()));
+        assert!(stack.is_shutdown());
+    }
+}
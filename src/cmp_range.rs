@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
 
 /// Compares `value` to `lower` and `upper`, where `lower <= upper`, and
 /// reports on where `value` falls with respect to the range.
@@ -24,26 +25,165 @@ pub fn cmp_range<T: Ord + std::fmt::Debug>(value: T, lower: T, upper: T) -> Rang
     }
 }
 
-/// Return type of [`cmp_range()`]
+/// Like [`cmp_range()`], but compares `value` to the bounds of `range`, which
+/// may be any [`RangeBounds`] implementation.
+///
+/// Either end of `range` may be inclusive, exclusive, or unbounded.  A value
+/// is only reported as equal to a bound (via [`RangeOrdering::EqLower`],
+/// [`RangeOrdering::EqUpper`], or [`RangeOrdering::EqBoth`]) if that bound is
+/// inclusive; a value equal to an exclusive bound is reported as outside the
+/// range.  If `range` is empty (e.g., `5..5`), values greater than or equal to
+/// the lower bound are reported as [`RangeOrdering::Greater`].
+///
+/// # Panics
+///
+/// Panics if the lower bound of `range` is greater than the upper bound
+pub fn cmp_range_bounds<T, R>(value: &T, range: R) -> RangeOrdering
+where
+    T: Ord + std::fmt::Debug,
+    R: RangeBounds<T>,
+{
+    let bounds = (range.start_bound(), range.end_bound());
+    match try_cmp_range(value, bounds) {
+        Some(r) => r,
+        None => panic!(
+            "cmp_range_bounds: expected lower <= upper; got lower={:?}, upper={:?}",
+            bounds.0, bounds.1
+        ),
+    }
+}
+
+/// Like [`cmp_range_bounds()`], but returns `None` instead of panicking if
+/// the lower bound of `range` is greater than the upper bound
+pub fn try_cmp_range<T: Ord, R: RangeBounds<T>>(value: &T, range: R) -> Option<RangeOrdering> {
+    try_cmp_range_by(value, range, T::cmp)
+}
+
+/// Like [`cmp_range_bounds()`], but values are compared using the given
+/// comparison function.  This can be used with types that do not implement
+/// [`Ord`], such as floats via [`f64::total_cmp()`]:
+///
+/// ```
+/// # use rswodlib::cmp_range::{cmp_range_by, RangeOrdering};
+/// assert_eq!(cmp_range_by(&0.5, 0.0..1.0, f64::total_cmp), RangeOrdering::Between);
+/// assert_eq!(cmp_range_by(&1.0, 0.0..1.0, f64::total_cmp), RangeOrdering::Greater);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the lower bound of `range` is greater than the upper bound
+pub fn cmp_range_by<T, R, F>(value: &T, range: R, cmp: F) -> RangeOrdering
+where
+    R: RangeBounds<T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    try_cmp_range_by(value, range, cmp).expect("cmp_range_by: expected lower <= upper")
+}
+
+/// Like [`cmp_range_by()`], but returns `None` instead of panicking if the
+/// lower bound of `range` is greater than the upper bound
+pub fn try_cmp_range_by<T, R, F>(value: &T, range: R, mut cmp: F) -> Option<RangeOrdering>
+where
+    R: RangeBounds<T>,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if let (Some(lower), Some(upper)) = (
+        bound_value(range.start_bound()),
+        bound_value(range.end_bound()),
+    ) && cmp(lower, upper) == Ordering::Greater
+    {
+        return None;
+    }
+    let eq_lower = match range.start_bound() {
+        Bound::Included(lower) => match cmp(value, lower) {
+            Ordering::Less => return Some(RangeOrdering::Less),
+            ord => ord == Ordering::Equal,
+        },
+        Bound::Excluded(lower) => {
+            if cmp(value, lower) != Ordering::Greater {
+                return Some(RangeOrdering::Less);
+            }
+            false
+        }
+        Bound::Unbounded => false,
+    };
+    let eq_upper = match range.end_bound() {
+        Bound::Included(upper) => match cmp(value, upper) {
+            Ordering::Greater => return Some(RangeOrdering::Greater),
+            ord => ord == Ordering::Equal,
+        },
+        Bound::Excluded(upper) => {
+            if cmp(value, upper) != Ordering::Less {
+                return Some(RangeOrdering::Greater);
+            }
+            false
+        }
+        Bound::Unbounded => false,
+    };
+    Some(match (eq_lower, eq_upper) {
+        (false, false) => RangeOrdering::Between,
+        (true, false) => RangeOrdering::EqLower,
+        (false, true) => RangeOrdering::EqUpper,
+        (true, true) => RangeOrdering::EqBoth,
+    })
+}
+
+fn bound_value<T>(bound: Bound<&T>) -> Option<&T> {
+    match bound {
+        Bound::Included(x) | Bound::Excluded(x) => Some(x),
+        Bound::Unbounded => None,
+    }
+}
+
+/// Like [`cmp_range_bounds()`], but values are compared by the keys returned
+/// by the given function, which is applied to `value` and to each bound of `range`
+///
+/// # Panics
+///
+/// Panics if the key of the lower bound of `range` is greater than that of
+/// the upper bound
+pub fn cmp_range_by_key<T, R, F, K>(value: &T, range: R, mut f: F) -> RangeOrdering
+where
+    R: RangeBounds<T>,
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    cmp_range_by(value, range, |a, b| f(a).cmp(&f(b)))
+}
+
+/// Return type of [`cmp_range()`] and related functions
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 pub enum RangeOrdering {
-    /// Returned when `value` is less than `lower`
+    /// Returned when `value` is less than the lower bound, or equal to it if
+    /// it is exclusive
     Less,
-    /// Returned when `value` equals `lower` and is less than `upper`
+    /// Returned when `value` equals the inclusive lower bound and is less
+    /// than the upper bound
     EqLower,
-    /// Returned when `value` is between `lower` and `upper`
+    /// Returned when `value` is strictly between the bounds
     Between,
-    /// Returned when `value` equals both `lower` and `upper`
+    /// Returned when `value` equals both bounds, which are inclusive
     EqBoth,
-    /// Returned when `value` equals `upper` and is greater than `lower`
+    /// Returned when `value` equals the inclusive upper bound and is greater
+    /// than the lower bound
     EqUpper,
-    /// Returned when `value` is greater than `upper`
+    /// Returned when `value` is greater than the upper bound, or equal to it
+    /// if it is exclusive
     Greater,
 }
 
+impl RangeOrdering {
+    /// Returns `true` if the value lies within the range
+    pub fn is_within(self) -> bool {
+        !matches!(self, RangeOrdering::Less | RangeOrdering::Greater)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use RangeOrdering::*;
+    use rstest::rstest;
 
     #[test]
     fn cmp_nontrivial_range() {
@@ -66,4 +206,93 @@ mod tests {
         assert_eq!(cmp_range(8, 7, 7), Greater);
         assert_eq!(cmp_range(10, 7, 7), Greater);
     }
+
+    #[test]
+    fn cmp_inclusive_bounds() {
+        assert_eq!(cmp_range_bounds(&4, 5..=10), Less);
+        assert_eq!(cmp_range_bounds(&5, 5..=10), EqLower);
+        assert_eq!(cmp_range_bounds(&6, 5..=10), Between);
+        assert_eq!(cmp_range_bounds(&10, 5..=10), EqUpper);
+        assert_eq!(cmp_range_bounds(&11, 5..=10), Greater);
+        assert_eq!(cmp_range_bounds(&7, 7..=7), EqBoth);
+    }
+
+    #[rstest]
+    #[case(4, Less)]
+    #[case(5, EqLower)]
+    #[case(9, Between)]
+    #[case(10, Greater)]
+    fn cmp_half_open(#[case] value: i32, #[case] expected: RangeOrdering) {
+        assert_eq!(cmp_range_bounds(&value, 5..10), expected);
+    }
+
+    #[rstest]
+    #[case(5, Less)]
+    #[case(6, Between)]
+    #[case(10, EqUpper)]
+    #[case(11, Greater)]
+    fn cmp_exclusive_lower(#[case] value: i32, #[case] expected: RangeOrdering) {
+        let range = (Bound::Excluded(5), Bound::Included(10));
+        assert_eq!(cmp_range_bounds(&value, range), expected);
+    }
+
+    #[test]
+    fn cmp_unbounded() {
+        assert_eq!(cmp_range_bounds(&i32::MIN, ..10), Between);
+        assert_eq!(cmp_range_bounds(&10, ..10), Greater);
+        assert_eq!(cmp_range_bounds(&10, ..=10), EqUpper);
+        assert_eq!(cmp_range_bounds(&5, 5..), EqLower);
+        assert_eq!(cmp_range_bounds(&4, 5..), Less);
+        assert_eq!(cmp_range_bounds(&i32::MAX, 5..), Between);
+        assert_eq!(cmp_range_bounds(&0, ..), Between);
+    }
+
+    #[test]
+    fn cmp_empty_range() {
+        assert_eq!(cmp_range_bounds(&4, 5..5), Less);
+        assert_eq!(cmp_range_bounds(&5, 5..5), Greater);
+        assert_eq!(cmp_range_bounds(&6, 5..5), Greater);
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn try_cmp_inverted_range() {
+        assert_eq!(try_cmp_range(&5, 10..=5), None);
+        assert_eq!(try_cmp_range(&5, 10..5), None);
+        assert_eq!(try_cmp_range(&5, 5..=10), Some(EqLower));
+    }
+
+    #[test]
+    #[should_panic(expected = "cmp_range_bounds: expected lower <= upper")]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn cmp_inverted_range() {
+        cmp_range_bounds(&5, 10..=5);
+    }
+
+    #[test]
+    fn cmp_floats() {
+        assert_eq!(cmp_range_by(&-0.0, 0.0..=1.0, f64::total_cmp), Less);
+        assert_eq!(cmp_range_by(&0.0, 0.0..=1.0, f64::total_cmp), EqLower);
+        assert_eq!(cmp_range_by(&f64::NAN, 0.0.., f64::total_cmp), Between);
+        assert_eq!(try_cmp_range_by(&0.5, 1.0..0.0, f64::total_cmp), None);
+    }
+
+    #[test]
+    fn cmp_by_key() {
+        assert_eq!(
+            cmp_range_by_key(&"apple", "Apple"..="Banana", |s| s.to_lowercase()),
+            EqLower
+        );
+        assert_eq!(cmp_range_by_key(&-7, 5..=10, |n: &i32| n.abs()), Between);
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(!Less.is_within());
+        assert!(EqLower.is_within());
+        assert!(Between.is_within());
+        assert!(EqBoth.is_within());
+        assert!(EqUpper.is_within());
+        assert!(!Greater.is_within());
+    }
 }
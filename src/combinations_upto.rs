@@ -0,0 +1,267 @@
+use std::iter::FusedIterator;
+
+/// Returns an iterator over all `k`-element subsets of `0..n`, each given as
+/// a strictly increasing `Vec`, in lexicographic order.
+///
+/// If `k` is zero, the iterator yields a single empty `Vec`; if `k` is
+/// greater than `n`, the iterator is empty.
+///
+/// Each combination's position in the output can be computed with
+/// [`CombinationsUpto::rank()`], and [`Iterator::nth()`] skips ahead in
+/// `O(n * k)` time, so an interrupted traversal can be resumed from a saved
+/// index.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::combinations_upto::combinations_upto;
+/// let mut iter = combinations_upto(4, 2);
+/// assert_eq!(iter.len(), 6);
+/// assert_eq!(iter.next(), Some(vec![0, 1]));
+/// assert_eq!(iter.next(), Some(vec![0, 2]));
+/// assert_eq!(iter.rank(&[1, 3]), Some(4));
+/// assert_eq!(iter.nth(2), Some(vec![1, 3]));
+/// assert_eq!(iter.next(), Some(vec![2, 3]));
+/// assert_eq!(iter.next(), None);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the number of combinations exceeds `usize::MAX`
+pub fn combinations_upto(n: usize, k: usize) -> CombinationsUpto {
+    let len = binomial(n, k).expect("number of combinations should not exceed usize::MAX");
+    CombinationsUpto {
+        n,
+        len,
+        pos: 0,
+        next: (0..k).collect(),
+    }
+}
+
+/// Iterator returned by [`combinations_upto()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CombinationsUpto {
+    n: usize,
+    len: usize,
+    pos: usize,
+    next: Vec<usize>,
+}
+
+impl CombinationsUpto {
+    fn k(&self) -> usize {
+        self.next.len()
+    }
+
+    /// Returns the number of combinations that have been yielded so far,
+    /// which is also the rank of the next combination to be yielded
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the index of `combo` in the complete output of this iterator,
+    /// or `None` if `combo` is not part of the output (i.e., if it is not a
+    /// strictly increasing sequence of `k` elements of `0..n`)
+    pub fn rank(&self, combo: &[usize]) -> Option<usize> {
+        let k = self.k();
+        if combo.len() != k
+            || combo.iter().any(|&x| x >= self.n)
+            || !combo.iter().zip(combo.iter().skip(1)).all(|(a, b)| a < b)
+        {
+            return None;
+        }
+        let mut rank = 0;
+        let mut x = 0;
+        for (t, &c) in combo.iter().enumerate() {
+            while x < c {
+                rank += self.count_after(x, t);
+                x += 1;
+            }
+            x += 1;
+        }
+        Some(rank)
+    }
+
+    /// Returns the combination at index `index` in the complete output of
+    /// this iterator, or `None` if `index` is out of range
+    pub fn unrank(&self, mut index: usize) -> Option<Vec<usize>> {
+        if index >= self.len {
+            return None;
+        }
+        let k = self.k();
+        let mut combo = Vec::with_capacity(k);
+        let mut x = 0;
+        for t in 0..k {
+            loop {
+                let c = self.count_after(x, t);
+                if index < c {
+                    break;
+                }
+                index -= c;
+                x += 1;
+            }
+            combo.push(x);
+            x += 1;
+        }
+        Some(combo)
+    }
+
+    /// Returns the number of combinations whose element at position `t` is
+    /// `x` and whose preceding elements are fixed
+    fn count_after(&self, x: usize, t: usize) -> usize {
+        // This is always at most `self.len`, so it cannot overflow.
+        binomial(self.n - x - 1, self.k() - t - 1).unwrap_or(usize::MAX)
+    }
+
+    /// Advance `self.next` to the lexicographically next combination
+    fn advance(&mut self) {
+        let k = self.k();
+        let limit = self.n - k;
+        if let Some(t) = self
+            .next
+            .iter()
+            .enumerate()
+            .rposition(|(t, &c)| c < limit + t)
+        {
+            let mut x = self.next[t];
+            for c in &mut self.next[t..] {
+                x += 1;
+                *c = x;
+            }
+        }
+    }
+}
+
+impl Iterator for CombinationsUpto {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.pos >= self.len {
+            return None;
+        }
+        let combo = self.next.clone();
+        self.pos += 1;
+        if self.pos < self.len {
+            self.advance();
+        }
+        Some(combo)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Vec<usize>> {
+        if let Some(combo) = self.pos.checked_add(n).and_then(|i| self.unrank(i)) {
+            self.pos += n;
+            self.next = combo;
+            self.next()
+        } else {
+            self.pos = self.len;
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sz = self.len - self.pos;
+        (sz, Some(sz))
+    }
+}
+
+impl FusedIterator for CombinationsUpto {}
+
+impl ExactSizeIterator for CombinationsUpto {}
+
+/// Returns the binomial coefficient `n` choose `k`, or `None` on overflow
+fn binomial(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    let k = k.min(n - k);
+    let mut acc = 1u128;
+    for i in 0..k {
+        // `acc` is always a binomial coefficient that fits in a `usize`, so
+        // this multiplication cannot overflow a `u128`.
+        acc = acc * (n - i) as u128 / (i + 1) as u128;
+        if usize::try_from(acc).is_err() {
+            return None;
+        }
+    }
+    usize::try_from(acc).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross_upto_n::cross_upto_n;
+    use proptest::prelude::*;
+
+    fn brute_force(n: usize, k: usize) -> Vec<Vec<usize>> {
+        cross_upto_n(&vec![n; k])
+            .filter(|v| v.iter().zip(v.iter().skip(1)).all(|(a, b)| a < b))
+            .collect()
+    }
+
+    #[test]
+    fn test_small() {
+        for n in 0..7 {
+            for k in 0..=n + 1 {
+                let expected = brute_force(n, k);
+                let mut iter = combinations_upto(n, k);
+                assert_eq!(iter.len(), expected.len(), "n={n}, k={k}");
+                for (r, combo) in expected.iter().enumerate() {
+                    assert_eq!(iter.position(), r);
+                    assert_eq!(iter.rank(combo), Some(r));
+                    assert_eq!(iter.unrank(r).as_ref(), Some(combo));
+                    assert_eq!(iter.next().as_ref(), Some(combo));
+                }
+                assert_eq!(iter.next(), None);
+                assert_eq!(iter.unrank(expected.len()), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_invalid() {
+        let iter = combinations_upto(5, 3);
+        assert_eq!(iter.rank(&[0, 1]), None);
+        assert_eq!(iter.rank(&[0, 2, 2]), None);
+        assert_eq!(iter.rank(&[2, 1, 3]), None);
+        assert_eq!(iter.rank(&[1, 2, 5]), None);
+    }
+
+    #[test]
+    fn test_binomial() {
+        assert_eq!(binomial(0, 0), Some(1));
+        assert_eq!(binomial(5, 2), Some(10));
+        assert_eq!(binomial(2, 5), Some(0));
+        assert_eq!(binomial(67, 33), Some(14_226_520_737_620_288_370));
+        assert_eq!(binomial(68, 34), None);
+        assert_eq!(binomial(usize::MAX, 1), Some(usize::MAX));
+        assert_eq!(binomial(usize::MAX, usize::MAX - 1), Some(usize::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "number of combinations should not exceed usize::MAX")]
+    fn test_overflow() {
+        let _ = combinations_upto(100, 50);
+    }
+
+    #[test]
+    fn test_large() {
+        let mut iter = combinations_upto(60, 30);
+        let last = iter.len() - 1;
+        let top = (30..60).collect::<Vec<_>>();
+        assert_eq!(iter.unrank(last).as_ref(), Some(&top));
+        assert_eq!(iter.rank(&top), Some(last));
+        assert_eq!(iter.nth(last), Some(top));
+        assert_eq!(iter.next(), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_nth(n in 0usize..10, k in 0usize..6, skip1 in 0usize..150, skip2 in 0usize..150) {
+            let expected = combinations_upto(n, k).collect::<Vec<_>>();
+            let mut iter = combinations_upto(n, k);
+            prop_assert_eq!(iter.nth(skip1), expected.get(skip1).cloned());
+            prop_assert_eq!(iter.next(), expected.get(skip1 + 1).cloned());
+            prop_assert_eq!(iter.nth(skip2), expected.get(skip1 + skip2 + 2).cloned());
+            prop_assert_eq!(iter.len(), expected.len().saturating_sub(skip1 + skip2 + 3));
+        }
+    }
+}
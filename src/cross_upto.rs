@@ -20,57 +20,232 @@ pub fn cross_upto(a: usize, b: usize) -> CrossUpto {
     CrossUpto::new(a, b)
 }
 
+/// Iterator returned by [`cross_upto()`]
+///
+/// In addition to being a [`DoubleEndedIterator`] and an
+/// [`ExactSizeIterator`], `CrossUpto` supports skipping over elements in
+/// constant time via [`Iterator::nth()`] and
+/// [`DoubleEndedIterator::nth_back()`] and can be divided into two iterators
+/// with [`CrossUpto::split_at()`].  When the `rayon` feature is enabled,
+/// `CrossUpto` also implements rayon's `IntoParallelIterator`, producing an
+/// `IndexedParallelIterator`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct CrossUpto {
     max_a: usize,
     max_b: usize,
-    a: usize,
-    b: usize,
+    // The next pair to yield from the front
+    front: (usize, usize),
+    // The pair after the next pair to yield from the back
+    back: (usize, usize),
 }
 
 impl CrossUpto {
     fn new(max_a: usize, max_b: usize) -> CrossUpto {
+        let back = if max_b == 0 { (0, 0) } else { (max_a, 0) };
         CrossUpto {
             max_a,
             max_b,
-            a: 0,
-            b: 0,
+            front: (0, 0),
+            back,
         }
     }
+
+    fn is_exhausted(&self) -> bool {
+        self.front >= self.back
+    }
+
+    /// Returns the pair `n` positions after `pos`, or `None` on overflow
+    fn advance(&self, (a, b): (usize, usize), n: usize) -> Option<(usize, usize)> {
+        let skip_a = n / self.max_b;
+        let skip_b = n % self.max_b;
+        let room = self.max_b - b;
+        let (carry, b) = if skip_b >= room {
+            (1, skip_b - room)
+        } else {
+            (0, b + skip_b)
+        };
+        let a = a.checked_add(skip_a)?.checked_add(carry)?;
+        Some((a, b))
+    }
+
+    /// Returns the pair `n` positions before `pos`, or `None` on underflow
+    fn retreat(&self, (a, b): (usize, usize), n: usize) -> Option<(usize, usize)> {
+        let skip_a = n / self.max_b;
+        let skip_b = n % self.max_b;
+        let (borrow, b) = if skip_b > b {
+            (1, self.max_b - (skip_b - b))
+        } else {
+            (0, b - skip_b)
+        };
+        let a = a.checked_sub(skip_a)?.checked_sub(borrow)?;
+        Some((a, b))
+    }
+
+    /// Divide the remaining elements into two iterators, the first of which
+    /// yields the first `index` elements and the second of which yields the
+    /// rest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib::cross_upto::cross_upto;
+    /// let (left, right) = cross_upto(3, 2).split_at(3);
+    /// assert_eq!(left.collect::<Vec<_>>(), [(0, 0), (0, 1), (1, 0)]);
+    /// assert_eq!(right.collect::<Vec<_>>(), [(1, 1), (2, 0), (2, 1)]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of remaining elements
+    pub fn split_at(self, index: usize) -> (CrossUpto, CrossUpto) {
+        let mid = if index == 0 {
+            Some(self.front)
+        } else if self.is_exhausted() {
+            None
+        } else {
+            self.advance(self.front, index)
+        };
+        let Some(mid) = mid.filter(|&mid| mid <= self.back) else {
+            panic!("CrossUpto::split_at: index out of bounds");
+        };
+        let left = CrossUpto { back: mid, ..self };
+        let right = CrossUpto { front: mid, ..self };
+        (left, right)
+    }
 }
 
 impl Iterator for CrossUpto {
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<(usize, usize)> {
-        if self.a >= self.max_a || self.b == self.max_b {
+        self.nth(0)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<(usize, usize)> {
+        if self.is_exhausted() {
             return None;
         }
-        let p = (self.a, self.b);
-        self.b += 1;
-        if self.b == self.max_b {
-            self.b = 0;
-            self.a += 1;
+        match self.advance(self.front, n) {
+            Some(p) if p < self.back => {
+                self.front = self.advance(p, 1).unwrap_or(self.back);
+                Some(p)
+            }
+            _ => {
+                self.front = self.back;
+                None
+            }
         }
-        Some(p)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        if self.a >= self.max_a || self.b == self.max_b {
+        if self.is_exhausted() {
             return (0, Some(0));
         }
-        let sz = self.max_b * (self.max_a - self.a) - self.b;
+        let sz = self.max_b * (self.back.0 - self.front.0) + self.back.1 - self.front.1;
         (sz, Some(sz))
     }
 }
 
+impl DoubleEndedIterator for CrossUpto {
+    fn next_back(&mut self) -> Option<(usize, usize)> {
+        self.nth_back(0)
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<(usize, usize)> {
+        if self.is_exhausted() {
+            return None;
+        }
+        match self.retreat(self.back, n).and_then(|p| self.retreat(p, 1)) {
+            Some(p) if p >= self.front => {
+                self.back = p;
+                Some(p)
+            }
+            _ => {
+                self.back = self.front;
+                None
+            }
+        }
+    }
+}
+
 impl FusedIterator for CrossUpto {}
 
 impl ExactSizeIterator for CrossUpto {}
 
+#[cfg(feature = "rayon")]
+mod par {
+    use super::CrossUpto;
+    use rayon::iter::plumbing::{Consumer, Producer, ProducerCallback, UnindexedConsumer, bridge};
+    use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+    impl IntoParallelIterator for CrossUpto {
+        type Iter = ParCrossUpto;
+        type Item = (usize, usize);
+
+        fn into_par_iter(self) -> ParCrossUpto {
+            ParCrossUpto { inner: self }
+        }
+    }
+
+    /// Parallel iterator over the pairs yielded by a [`CrossUpto`]
+    #[derive(Clone, Debug)]
+    pub struct ParCrossUpto {
+        inner: CrossUpto,
+    }
+
+    impl ParallelIterator for ParCrossUpto {
+        type Item = (usize, usize);
+
+        fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where
+            C: UnindexedConsumer<Self::Item>,
+        {
+            bridge(self, consumer)
+        }
+
+        fn opt_len(&self) -> Option<usize> {
+            Some(self.inner.len())
+        }
+    }
+
+    impl IndexedParallelIterator for ParCrossUpto {
+        fn len(&self) -> usize {
+            self.inner.len()
+        }
+
+        fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+            bridge(self, consumer)
+        }
+
+        fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+            callback.callback(CrossUptoProducer(self.inner))
+        }
+    }
+
+    struct CrossUptoProducer(CrossUpto);
+
+    impl Producer for CrossUptoProducer {
+        type Item = (usize, usize);
+        type IntoIter = CrossUpto;
+
+        fn into_iter(self) -> CrossUpto {
+            self.0
+        }
+
+        fn split_at(self, index: usize) -> (Self, Self) {
+            let (left, right) = self.0.split_at(index);
+            (CrossUptoProducer(left), CrossUptoProducer(right))
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub use par::ParCrossUpto;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_3x2() {
@@ -115,4 +290,108 @@ mod tests {
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_rev() {
+        let pairs = cross_upto(3, 2).rev().collect::<Vec<_>>();
+        assert_eq!(pairs, [(2, 1), (2, 0), (1, 1), (1, 0), (0, 1), (0, 0)]);
+    }
+
+    #[test]
+    fn test_both_ends() {
+        let mut iter = cross_upto(2, 2);
+        assert_eq!(iter.next(), Some((0, 0)));
+        assert_eq!(iter.next_back(), Some((1, 1)));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next_back(), Some((1, 0)));
+        assert_eq!(iter.next(), Some((0, 1)));
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_nth_huge() {
+        let mut iter = cross_upto(usize::MAX, usize::MAX);
+        assert_eq!(iter.nth(usize::MAX), Some((1, 0)));
+        assert_eq!(iter.nth(usize::MAX - 2), Some((1, usize::MAX - 1)));
+        assert_eq!(iter.next(), Some((2, 0)));
+        assert_eq!(iter.next_back(), Some((usize::MAX - 1, usize::MAX - 1)));
+        assert_eq!(
+            iter.nth_back(usize::MAX),
+            Some((usize::MAX - 2, usize::MAX - 2))
+        );
+    }
+
+    #[test]
+    fn test_nth_past_end() {
+        let mut iter = cross_upto(3, 2);
+        assert_eq!(iter.nth(6), None);
+        assert_eq!(iter.next(), None);
+        let mut iter = cross_upto(3, 2);
+        assert_eq!(iter.nth_back(6), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_split_at() {
+        let mut iter = cross_upto(4, 3);
+        iter.next();
+        iter.next_back();
+        let (left, right) = iter.clone().split_at(4);
+        assert_eq!(left.len(), 4);
+        assert_eq!(right.len(), 6);
+        assert!(left.chain(right).eq(iter.clone()));
+        let (left, right) = iter.clone().split_at(0);
+        assert_eq!(left.len(), 0);
+        assert!(right.eq(iter.clone()));
+        let (left, right) = iter.clone().split_at(10);
+        assert!(left.eq(iter));
+        assert_eq!(right.len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "CrossUpto::split_at: index out of bounds")]
+    fn test_split_at_out_of_bounds() {
+        let _ = cross_upto(3, 2).split_at(7);
+    }
+
+    #[test]
+    fn test_split_at_empty() {
+        let (left, right) = cross_upto(3, 0).split_at(0);
+        assert_eq!(left.len(), 0);
+        assert_eq!(right.len(), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_random_access(a in 0usize..6, b in 0usize..6, n in 0usize..40, m in 0usize..40) {
+            let expected = cross_upto(a, b).collect::<Vec<_>>();
+            prop_assert_eq!(cross_upto(a, b).nth(n), expected.get(n).copied());
+            prop_assert_eq!(cross_upto(a, b).nth_back(n), expected.iter().rev().nth(n).copied());
+            let mut iter = cross_upto(a, b);
+            let _ = iter.nth(n);
+            let _ = iter.nth_back(m);
+            let middle = expected.get(n + 1..expected.len().saturating_sub(m + 1)).unwrap_or_default();
+            prop_assert_eq!(iter.len(), middle.len());
+            prop_assert_eq!(iter.collect::<Vec<_>>(), middle);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_iter() {
+        use rayon::prelude::*;
+        let iter = cross_upto(100, 37);
+        let par = iter.clone().into_par_iter();
+        assert_eq!(par.len(), 3700);
+        let pairs = par.collect::<Vec<_>>();
+        assert_eq!(pairs, iter.collect::<Vec<_>>());
+        let sum = cross_upto(100, 37)
+            .into_par_iter()
+            .with_max_len(1)
+            .map(|(a, b)| a * b)
+            .sum::<usize>();
+        assert_eq!(sum, (0..100).sum::<usize>() * (0..37).sum::<usize>());
+    }
 }
@@ -0,0 +1,414 @@
+use std::iter::FusedIterator;
+
+/// Returns an iterator over all `Vec`s `v` of the same length as `bounds` in
+/// which `v[i]` is in `0..bounds[i]` for each `i`, in lexicographic order.
+///
+/// If `bounds` is empty, the iterator yields a single empty `Vec`.
+///
+/// The returned iterator supports efficient random access via
+/// [`Iterator::nth()`] and [`DoubleEndedIterator::nth_back()`].
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::cross_upto_n::cross_upto_n;
+/// let mut iter = cross_upto_n(&[2, 1, 3]);
+/// assert_eq!(iter.len(), 6);
+/// assert_eq!(iter.next(), Some(vec![0, 0, 0]));
+/// assert_eq!(iter.next(), Some(vec![0, 0, 1]));
+/// assert_eq!(iter.next_back(), Some(vec![1, 0, 2]));
+/// assert_eq!(iter.nth(2), Some(vec![1, 0, 1]));
+/// assert_eq!(iter.next(), None);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the product of `bounds` exceeds `usize::MAX`
+pub fn cross_upto_n(bounds: &[usize]) -> CrossUptoN {
+    CrossUptoN {
+        cursor: Cursor::new(bounds),
+        bounds: bounds.to_vec(),
+    }
+}
+
+/// Like [`cross_upto_n()`], but the number of dimensions is fixed at compile
+/// time and the iterator yields arrays
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::cross_upto_n::cross_upto_array;
+/// let mut iter = cross_upto_array([2, 2]);
+/// assert_eq!(iter.next(), Some([0, 0]));
+/// assert_eq!(iter.next(), Some([0, 1]));
+/// assert_eq!(iter.next(), Some([1, 0]));
+/// assert_eq!(iter.next(), Some([1, 1]));
+/// assert_eq!(iter.next(), None);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the product of `bounds` exceeds `usize::MAX`
+pub fn cross_upto_array<const N: usize>(bounds: [usize; N]) -> CrossUptoArray<N> {
+    CrossUptoArray {
+        cursor: Cursor::new(&bounds),
+        bounds,
+    }
+}
+
+/// Returns an iterator over the cartesian product of the given iterators,
+/// i.e., over all `Vec`s containing one item from each iterator, in
+/// lexicographic order of the items' positions.
+///
+/// The items of each iterator are collected up front, which is what allows
+/// the returned iterator to support efficient random access.  If `iters` is
+/// empty, the iterator yields a single empty `Vec`.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::cross_upto_n::cartesian_product;
+/// let iter = cartesian_product(["ab".chars(), "xyz".chars()]);
+/// assert_eq!(iter.len(), 6);
+/// let words = iter.map(String::from_iter).collect::<Vec<_>>();
+/// assert_eq!(words, ["ax", "ay", "az", "bx", "by", "bz"]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the product of the iterators' lengths exceeds `usize::MAX`
+pub fn cartesian_product<I, J>(iters: I) -> CartesianProduct<J::Item>
+where
+    I: IntoIterator<Item = J>,
+    J: IntoIterator,
+    J::Item: Clone,
+{
+    let pools = iters
+        .into_iter()
+        .map(|it| it.into_iter().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let lengths = pools.iter().map(Vec::len).collect::<Vec<_>>();
+    CartesianProduct {
+        indices: cross_upto_n(&lengths),
+        pools,
+    }
+}
+
+/// Iterator returned by [`cross_upto_n()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CrossUptoN {
+    bounds: Vec<usize>,
+    cursor: Cursor,
+}
+
+impl CrossUptoN {
+    fn decode(&self, index: usize) -> Vec<usize> {
+        let mut v = vec![0; self.bounds.len()];
+        decode(&self.bounds, index, &mut v);
+        v
+    }
+}
+
+impl Iterator for CrossUptoN {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        self.cursor.next().map(|i| self.decode(i))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Vec<usize>> {
+        self.cursor.nth(n).map(|i| self.decode(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sz = self.cursor.len();
+        (sz, Some(sz))
+    }
+}
+
+impl DoubleEndedIterator for CrossUptoN {
+    fn next_back(&mut self) -> Option<Vec<usize>> {
+        self.cursor.next_back().map(|i| self.decode(i))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Vec<usize>> {
+        self.cursor.nth_back(n).map(|i| self.decode(i))
+    }
+}
+
+impl FusedIterator for CrossUptoN {}
+
+impl ExactSizeIterator for CrossUptoN {}
+
+/// Iterator returned by [`cross_upto_array()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CrossUptoArray<const N: usize> {
+    bounds: [usize; N],
+    cursor: Cursor,
+}
+
+impl<const N: usize> CrossUptoArray<N> {
+    fn decode(&self, index: usize) -> [usize; N] {
+        let mut v = [0; N];
+        decode(&self.bounds, index, &mut v);
+        v
+    }
+}
+
+impl<const N: usize> Iterator for CrossUptoArray<N> {
+    type Item = [usize; N];
+
+    fn next(&mut self) -> Option<[usize; N]> {
+        self.cursor.next().map(|i| self.decode(i))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<[usize; N]> {
+        self.cursor.nth(n).map(|i| self.decode(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sz = self.cursor.len();
+        (sz, Some(sz))
+    }
+}
+
+impl<const N: usize> DoubleEndedIterator for CrossUptoArray<N> {
+    fn next_back(&mut self) -> Option<[usize; N]> {
+        self.cursor.next_back().map(|i| self.decode(i))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<[usize; N]> {
+        self.cursor.nth_back(n).map(|i| self.decode(i))
+    }
+}
+
+impl<const N: usize> FusedIterator for CrossUptoArray<N> {}
+
+impl<const N: usize> ExactSizeIterator for CrossUptoArray<N> {}
+
+/// Iterator returned by [`cartesian_product()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CartesianProduct<T> {
+    pools: Vec<Vec<T>>,
+    indices: CrossUptoN,
+}
+
+impl<T: Clone> CartesianProduct<T> {
+    fn select(&self, indices: Vec<usize>) -> Vec<T> {
+        std::iter::zip(&self.pools, indices)
+            .filter_map(|(pool, i)| pool.get(i).cloned())
+            .collect()
+    }
+}
+
+impl<T: Clone> Iterator for CartesianProduct<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        self.indices.next().map(|ix| self.select(ix))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Vec<T>> {
+        self.indices.nth(n).map(|ix| self.select(ix))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indices.size_hint()
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for CartesianProduct<T> {
+    fn next_back(&mut self) -> Option<Vec<T>> {
+        self.indices.next_back().map(|ix| self.select(ix))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Vec<T>> {
+        self.indices.nth_back(n).map(|ix| self.select(ix))
+    }
+}
+
+impl<T: Clone> FusedIterator for CartesianProduct<T> {}
+
+impl<T: Clone> ExactSizeIterator for CartesianProduct<T> {}
+
+/// The range of linear indices (into the row-major enumeration of the
+/// product) that have yet to be yielded
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Cursor {
+    front: usize,
+    back: usize,
+}
+
+impl Cursor {
+    fn new(bounds: &[usize]) -> Cursor {
+        // A zero bound makes the product zero no matter how large the other
+        // bounds are, so don't let them overflow the multiplication.
+        let back = if bounds.contains(&0) {
+            0
+        } else {
+            bounds
+                .iter()
+                .try_fold(1usize, |acc, &b| acc.checked_mul(b))
+                .expect("product of bounds should not exceed usize::MAX")
+        };
+        Cursor { front: 0, back }
+    }
+
+    fn len(&self) -> usize {
+        self.back - self.front
+    }
+
+    fn next(&mut self) -> Option<usize> {
+        (self.front < self.back).then(|| {
+            self.front += 1;
+            self.front - 1
+        })
+    }
+
+    fn nth(&mut self, n: usize) -> Option<usize> {
+        if n >= self.len() {
+            self.front = self.back;
+            None
+        } else {
+            self.front += n;
+            self.next()
+        }
+    }
+
+    fn next_back(&mut self) -> Option<usize> {
+        (self.front < self.back).then(|| {
+            self.back -= 1;
+            self.back
+        })
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<usize> {
+        if n >= self.len() {
+            self.back = self.front;
+            None
+        } else {
+            self.back -= n;
+            self.next_back()
+        }
+    }
+}
+
+/// Convert the linear index `index` into a tuple of indices into each
+/// dimension, with the last dimension varying fastest
+fn decode(bounds: &[usize], mut index: usize, out: &mut [usize]) {
+    for (slot, &b) in std::iter::zip(out, bounds).rev() {
+        *slot = index % b;
+        index /= b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross_upto::cross_upto;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_2x3x2() {
+        let mut iter = cross_upto_n(&[2, 3, 2]);
+        let mut expected = Vec::new();
+        for a in 0..2 {
+            for b in 0..3 {
+                for c in 0..2 {
+                    expected.push(vec![a, b, c]);
+                }
+            }
+        }
+        for (i, v) in expected.into_iter().enumerate() {
+            assert_eq!(iter.size_hint(), (12 - i, Some(12 - i)));
+            assert_eq!(iter.next(), Some(v));
+        }
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_matches_cross_upto() {
+        let pairs = cross_upto_array([4, 3]).map(|[a, b]| (a, b));
+        assert!(pairs.eq(cross_upto(4, 3)));
+    }
+
+    #[test]
+    fn test_empty_bounds() {
+        let mut iter = cross_upto_n(&[]);
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(Vec::new()));
+        assert_eq!(iter.next(), None);
+        let mut iter = cross_upto_array([]);
+        assert_eq!(iter.next(), Some([]));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_zero_bound() {
+        let mut iter = cross_upto_n(&[3, 0, 2]);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_zero_bound_large_product() {
+        let mut iter = cross_upto_n(&[usize::MAX, 2, 0]);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        let mut iter = cross_upto_array([0, usize::MAX, usize::MAX]);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_nth() {
+        let mut iter = cross_upto_array([10, 10, 10]);
+        assert_eq!(iter.nth(123), Some([1, 2, 3]));
+        assert_eq!(iter.nth_back(0), Some([9, 9, 9]));
+        assert_eq!(iter.nth_back(100), Some([8, 9, 8]));
+        assert_eq!(iter.len(), 898 - 124);
+        assert_eq!(iter.nth(1000), None);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "product of bounds should not exceed usize::MAX")]
+    fn test_overflow() {
+        let _ = cross_upto_n(&[usize::MAX, 2]);
+    }
+
+    #[test]
+    fn test_cartesian_product() {
+        let mut iter = cartesian_product([vec![1, 2], vec![3], vec![4, 5]]);
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next(), Some(vec![1, 3, 4]));
+        assert_eq!(iter.next_back(), Some(vec![2, 3, 5]));
+        assert_eq!(iter.nth(1), Some(vec![2, 3, 4]));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_cartesian_product_empty() {
+        let mut iter = cartesian_product(Vec::<Vec<u8>>::new());
+        assert_eq!(iter.next(), Some(Vec::new()));
+        assert_eq!(iter.next(), None);
+        let mut iter = cartesian_product([vec![1, 2], vec![]]);
+        assert_eq!(iter.next(), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_nth_matches_next(bounds in prop::collection::vec(0usize..5, 0..5), n in 0usize..700) {
+            let expected = cross_upto_n(&bounds).collect::<Vec<_>>();
+            prop_assert_eq!(cross_upto_n(&bounds).nth(n), expected.get(n).cloned());
+            prop_assert_eq!(
+                cross_upto_n(&bounds).nth_back(n),
+                expected.iter().rev().nth(n).cloned()
+            );
+            prop_assert!(cross_upto_n(&bounds).rev().eq(expected.into_iter().rev()));
+        }
+    }
+}
@@ -1,4 +1,15 @@
+use std::any::Any;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
 use std::sync::{Condvar, Mutex};
+use std::thread::ScopedJoinHandle;
+use std::time::Duration;
 
 /// A synchronized stack of work items/jobs for use by a collection of
 /// concurrent workers that are both producers and consumers of jobs.
@@ -6,35 +17,187 @@ use std::sync::{Condvar, Mutex};
 /// After the stack is initialized with some starting jobs, each worker calls
 /// `handle_job()` (likely in a loop) or `handle_many_jobs()` with a function
 /// that takes a job and returns more jobs, which are pushed on top of the
-/// stack.  All work terminates when (a) all jobs have completed successfully,
-/// (b) a job has returned `Err`, or (c) `shutdown()` is called.
-#[derive(Debug)]
-pub struct JobStack<T> {
-    data: Mutex<JobStackData<T>>,
+/// stack.  All work terminates when (a) all jobs have completed, (b) a job
+/// has returned `Err` (or, depending on the [`ErrorPolicy`], enough jobs have
+/// returned `Err`), or (c) `shutdown()` is called.
+///
+/// Alternatively, `run()` can be used to spawn a pool of worker threads that
+/// process all of the jobs.
+///
+/// By default, jobs are stored in a `Vec` and processed in LIFO order, which
+/// results in a depth-first traversal.  Other orders can be used by
+/// constructing a `JobStack` with [`JobStack::with_queue()`] and a different
+/// [`JobQueue`] implementation: a `VecDeque` for FIFO order (a breadth-first
+/// traversal), a `BinaryHeap` or [`PriorityQueue`] for priority order, or any
+/// custom queue.  To instead give each worker thread its own queue and let
+/// idle workers steal from the others, use
+/// [`JobStack::run_work_stealing()`].
+///
+/// When jobs form a graph rather than a tree, duplicate jobs can be skipped
+/// by enabling deduplication with [`JobStack::dedup()`] or
+/// [`JobStack::dedup_by_key()`].
+///
+/// The progress of the jobs can be monitored by calling
+/// [`JobStack::snapshot()`] or by registering a callback with
+/// [`JobStack::on_progress()`], and [`JobStack::wait_idle()`] can be used to
+/// wait for all jobs to finish.
+///
+/// By default, the stack is shut down as soon as any job fails.  To instead
+/// let the remaining jobs run to completion, set an [`ErrorPolicy`] with
+/// [`JobStack::error_policy()`], and use [`JobStack::run_collecting()`] to
+/// get back every failure along with the job that caused it.
+pub struct JobStack<T, Q = Vec<T>> {
+    data: Mutex<JobStackData<Q>>,
     cond: Condvar,
+    visited: Option<Mutex<Visited<T>>>,
+    progress: Option<ProgressCallback>,
+    policy: ErrorPolicy,
+    // The jobs being processed by `run_work_stealing()`, which are not
+    // tracked in `data` while it runs
+    stealing: StealingCounts,
+    // Mirrors `data.shutdown` so that it can be checked without locking
+    shutdown: AtomicBool,
+    _items: PhantomData<fn(T) -> T>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct JobStackData<T> {
-    stack: Vec<T>,
+struct JobStackData<Q> {
+    queue: Q,
     jobs: usize,
     shutdown: bool,
+    max_depth: usize,
+    completed: usize,
+    failed: usize,
 }
 
 impl<T> JobStack<T> {
     pub fn new<I: IntoIterator<Item = T>>(items: I) -> Self {
-        let stack = Vec::from_iter(items);
-        let jobs = stack.len();
+        JobStack::with_queue(Vec::from_iter(items))
+    }
+}
+
+impl<T, Q: JobQueue<T>> JobStack<T, Q> {
+    /// Create a `JobStack` that stores its jobs in `queue`, starting with the
+    /// jobs already in `queue`
+    pub fn with_queue(queue: Q) -> Self {
+        let jobs = queue.len();
         JobStack {
             data: Mutex::new(JobStackData {
-                stack,
+                queue,
                 jobs,
                 shutdown: false,
+                max_depth: jobs,
+                completed: 0,
+                failed: 0,
             }),
             cond: Condvar::new(),
+            visited: None,
+            progress: None,
+            policy: ErrorPolicy::default(),
+            stealing: StealingCounts::default(),
+            shutdown: AtomicBool::new(false),
+            _items: PhantomData,
         }
     }
 
+    /// Enable deduplication of jobs: each new job returned by a job function
+    /// is discarded if an equal job has previously been added to the stack.
+    /// Duplicates among the jobs already on the stack are removed as well.
+    pub fn dedup(self) -> Self
+    where
+        T: Clone + Eq + Hash + Send + 'static,
+    {
+        self.dedup_by_key(T::clone)
+    }
+
+    /// Enable deduplication of jobs by key: each new job returned by a job
+    /// function is discarded if a job with the same key has previously been
+    /// added to the stack.  Duplicates among the jobs already on the stack
+    /// are removed as well.
+    ///
+    /// The jobs themselves need not be `'static`, so jobs that borrow from
+    /// the surrounding scope can be deduplicated by mapping them to owned
+    /// keys.
+    pub fn dedup_by_key<K, F>(mut self, mut key: F) -> Self
+    where
+        K: Eq + Hash + Send + 'static,
+        F: FnMut(&T) -> K + Send + 'static,
+    {
+        let mut seen = HashSet::new();
+        let mut visited = Visited {
+            is_new: Box::new(move |job| seen.insert(key(job))),
+            skipped: 0,
+        };
+        let Ok(data) = self.data.get_mut() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        let prelen = data.queue.len();
+        data.queue.retain(|job| visited.check(job));
+        data.jobs -= prelen - data.queue.len();
+        self.visited = Some(Mutex::new(visited));
+        self
+    }
+
+    /// Register a function to be called with a [`Snapshot`] of the stack's
+    /// state whenever a job is added, started, or finished and when the stack
+    /// is shut down.  To receive the snapshots on another thread, send them
+    /// over a channel from within the callback.
+    ///
+    /// The callback is called without holding any locks, and so it may call
+    /// methods of the `JobStack`.  However, when multiple workers are
+    /// running, the callback may be called concurrently, and snapshots may
+    /// arrive out of order.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(Snapshot) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressCallback(Box::new(callback)));
+        self
+    }
+
+    /// Set the policy that determines when a failed job causes the stack to
+    /// be shut down
+    pub fn error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the current numbers of pending, in-flight, completed, and
+    /// failed jobs, including those being processed by
+    /// [`JobStack::run_work_stealing()`]
+    pub fn snapshot(&self) -> Snapshot {
+        let Ok(data) = self.data.lock() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        self.snapshot_of(&data)
+    }
+
+    /// Block until there are no pending or in-flight jobs or until `timeout`
+    /// elapses, whichever comes first.  Returns `true` if the stack became
+    /// idle before the timeout.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let Ok(data) = self.data.lock() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        let busy = |data: &mut JobStackData<Q>| data.jobs > 0 || self.stealing.pending() > 0;
+        let Ok((mut data, _)) = self.cond.wait_timeout_while(data, timeout, busy) else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        !busy(&mut data)
+    }
+
+    /// Returns the number of duplicate jobs that have been discarded since
+    /// deduplication was enabled
+    pub fn duplicates_skipped(&self) -> usize {
+        self.visited.as_ref().map_or(0, |v| lock_visited(v).skipped)
+    }
+
+    /// Pop a job from the stack, process it with `f`, and push the new jobs
+    /// it returns onto the stack.  Returns `Ok(false)` without calling `f` if
+    /// all jobs have completed or the stack has been shut down.
+    ///
+    /// If `f` returns `Err`, the error is returned, and the stack is shut
+    /// down if its [`ErrorPolicy`] calls for it.
     pub fn handle_job<F, I, E>(&self, f: F) -> Result<bool, E>
     where
         F: FnOnce(T) -> Result<I, E>,
@@ -46,17 +209,22 @@ impl<T> JobStack<T> {
         match f(value) {
             Ok(iter) => {
                 self.extend(iter);
-                self.job_done();
+                self.job_done(true);
                 Ok(true)
             }
             Err(e) => {
-                self.job_done();
-                self.shutdown();
+                self.job_done(false);
                 Err(e)
             }
         }
     }
 
+    /// Repeatedly call `f` on jobs from the stack and push the new jobs it
+    /// returns onto the stack until all jobs have completed, the stack is
+    /// shut down, or `f` returns `Err`, in which case the error is returned.
+    ///
+    /// Whether an error also shuts down the stack for the other workers is
+    /// determined by the stack's [`ErrorPolicy`].
     pub fn handle_many_jobs<F, I, E>(&self, mut f: F) -> Result<(), E>
     where
         F: FnMut(T) -> Result<I, E>,
@@ -66,11 +234,10 @@ impl<T> JobStack<T> {
             match f(value) {
                 Ok(iter) => {
                     self.extend(iter);
-                    self.job_done();
+                    self.job_done(true);
                 }
                 Err(e) => {
-                    self.job_done();
-                    self.shutdown();
+                    self.job_done(false);
                     return Err(e);
                 }
             }
@@ -78,23 +245,291 @@ impl<T> JobStack<T> {
         Ok(())
     }
 
+    /// Process all jobs on the stack using `n_threads` scoped worker
+    /// threads.  For each job, `f` returns a result value for the job along
+    /// with any new jobs to push onto the stack.
+    ///
+    /// If `f` returns `Err`, the first error returned is returned once all
+    /// workers have stopped.  Under the default [`ErrorPolicy`], the stack is
+    /// shut down at the first error, and the remaining workers stop once they
+    /// finish their current jobs.  Otherwise, the results of all jobs (in
+    /// unspecified order) are returned along with statistics about the run.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the stack is shut down, and the panic is propagated
+    /// once all workers have stopped.
+    pub fn run<F, I, R, E>(&self, n_threads: NonZeroUsize, f: F) -> Result<RunReport<R>, E>
+    where
+        T: Send,
+        Q: Send,
+        F: Fn(T) -> Result<(R, I), E> + Sync,
+        I: IntoIterator<Item = T>,
+        R: Send,
+        E: Send,
+    {
+        let (results, errors) = self.run_workers(n_threads, f);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        let stats = RunStats {
+            jobs_processed: results.len(),
+            max_depth: self.max_depth(),
+            duplicates_skipped: self.duplicates_skipped(),
+        };
+        Ok(RunReport { results, stats })
+    }
+
+    /// Like [`JobStack::run()`], but instead of returning the first error,
+    /// every failure is collected.  A failing job function returns a
+    /// [`JobFailure`] containing the job (which the function hands back
+    /// rather than the stack having to clone every job in advance) and the
+    /// error it produced.  Combined with [`ErrorPolicy::KeepGoing`], this
+    /// allows a batch of jobs to run to completion and report all of its
+    /// failures at the end.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the stack is shut down, and the panic is propagated
+    /// once all workers have stopped.
+    pub fn run_collecting<F, I, R, E>(&self, n_threads: NonZeroUsize, f: F) -> BatchReport<T, R, E>
+    where
+        T: Send,
+        Q: Send,
+        F: Fn(T) -> Result<(R, I), JobFailure<T, E>> + Sync,
+        I: IntoIterator<Item = T>,
+        R: Send,
+        E: Send,
+    {
+        let (results, failures) = self.run_workers(n_threads, f);
+        let stats = RunStats {
+            jobs_processed: results.len() + failures.len(),
+            max_depth: self.max_depth(),
+            duplicates_skipped: self.duplicates_skipped(),
+        };
+        BatchReport {
+            results,
+            failures,
+            stats,
+        }
+    }
+
+    /// Like [`JobStack::run()`], but each worker thread keeps its own deque
+    /// of jobs instead of sharing the `JobStack`'s queue, which reduces
+    /// contention when jobs are short.  The jobs on the `JobStack` are first
+    /// distributed among the workers.  Each worker pushes the jobs it
+    /// produces onto the back of its own deque and takes its next job from
+    /// the back as well (i.e., in LIFO order), and a worker with an empty
+    /// deque steals the oldest job from the front of another worker's deque.
+    ///
+    /// The `JobStack`'s own queue (and thus its queue discipline) is not used
+    /// while this method is running, so it should not be combined with
+    /// concurrent calls to `handle_job()` or `handle_many_jobs()`.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the stack is shut down, and the panic is propagated
+    /// once all workers have stopped.
+    pub fn run_work_stealing<F, I, R, E>(
+        &self,
+        n_threads: NonZeroUsize,
+        f: F,
+    ) -> Result<RunReport<R>, E>
+    where
+        T: Send,
+        Q: Send,
+        F: Fn(T) -> Result<(R, I), E> + Sync,
+        I: IntoIterator<Item = T>,
+        R: Send,
+        E: Send,
+    {
+        let deques = std::iter::repeat_with(|| Mutex::new(VecDeque::new()))
+            .take(n_threads.get())
+            .collect::<Vec<_>>();
+        let initial = {
+            let Ok(mut data) = self.data.lock() else {
+                unreachable!("Mutex should not have been poisoned");
+            };
+            let mut i = 0;
+            while let Some(job) = data.queue.pop() {
+                lock_deque(&deques[i % deques.len()]).push_back(job);
+                i += 1;
+            }
+            // Move the jobs from `data` to `stealing` without releasing the
+            // lock so that `wait_idle()` never sees them as finished.
+            data.jobs -= i;
+            self.stealing.pending.store(i, atomic::Ordering::SeqCst);
+            self.stealing.queued.store(i, atomic::Ordering::SeqCst);
+            i
+        };
+        let pool = StealingPool {
+            deques,
+            counts: &self.stealing,
+            max_depth: AtomicUsize::new(initial),
+            idle: Mutex::new(()),
+            wakeup: Condvar::new(),
+        };
+        let errors = Mutex::new(Vec::new());
+        let job_done = |success: bool| {
+            let counter = if success {
+                &self.stealing.completed
+            } else {
+                &self.stealing.failed
+            };
+            let count = counter.fetch_add(1, atomic::Ordering::SeqCst) + 1;
+            if !success && self.policy.stops_after(count) {
+                self.shutdown();
+            }
+            if pool.job_done(self.is_shutdown()) {
+                self.notify_waiters();
+            }
+            self.report_progress();
+        };
+        let worker = |i: usize| {
+            let mut results = Vec::new();
+            while let Some(value) = pool.next_job(i, || self.is_shutdown()) {
+                self.report_progress();
+                match catch_unwind(AssertUnwindSafe(|| f(value))) {
+                    Ok(Ok((r, iter))) => {
+                        results.push(r);
+                        let jobs = self.unvisited(iter);
+                        if !jobs.is_empty() {
+                            pool.give(i, jobs);
+                            self.report_progress();
+                        }
+                        job_done(true);
+                    }
+                    Ok(Err(e)) => {
+                        job_done(false);
+                        let Ok(mut errors) = errors.lock() else {
+                            unreachable!("Mutex should not have been poisoned");
+                        };
+                        errors.push(e);
+                    }
+                    Err(payload) => {
+                        self.shutdown();
+                        job_done(false);
+                        resume_unwind(payload);
+                    }
+                }
+            }
+            results
+        };
+        let outcomes = std::thread::scope(|scope| {
+            let mut handles = Vec::with_capacity(n_threads.get());
+            for i in 0..n_threads.get() {
+                handles.push(scope.spawn(move || worker(i)));
+            }
+            handles
+                .into_iter()
+                .map(ScopedJoinHandle::join)
+                .collect::<Vec<_>>()
+        });
+        let snapshot = {
+            let Ok(mut data) = self.data.lock() else {
+                unreachable!("Mutex should not have been poisoned");
+            };
+            // Any jobs still queued were discarded by a shutdown.
+            data.completed += self.stealing.completed.swap(0, atomic::Ordering::SeqCst);
+            data.failed += self.stealing.failed.swap(0, atomic::Ordering::SeqCst);
+            self.stealing.pending.store(0, atomic::Ordering::SeqCst);
+            self.stealing.queued.store(0, atomic::Ordering::SeqCst);
+            self.cond.notify_all();
+            self.progress.is_some().then(|| self.snapshot_of(&data))
+        };
+        self.report(snapshot);
+        let (results, errors) = gather_results(outcomes, errors);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+        let stats = RunStats {
+            jobs_processed: results.len(),
+            max_depth: pool.max_depth.into_inner(),
+            duplicates_skipped: self.duplicates_skipped(),
+        };
+        Ok(RunReport { results, stats })
+    }
+
+    /// Process all jobs on the stack using `n_threads` scoped worker threads,
+    /// returning the results of the successful jobs and the errors from the
+    /// failed jobs, each in the order in which they occurred
+    fn run_workers<F, I, R, E>(&self, n_threads: NonZeroUsize, f: F) -> (Vec<R>, Vec<E>)
+    where
+        T: Send,
+        Q: Send,
+        F: Fn(T) -> Result<(R, I), E> + Sync,
+        I: IntoIterator<Item = T>,
+        R: Send,
+        E: Send,
+    {
+        let errors = Mutex::new(Vec::new());
+        let worker = || {
+            let mut results = Vec::new();
+            while let Some(value) = self.pop() {
+                match catch_unwind(AssertUnwindSafe(|| f(value))) {
+                    Ok(Ok((r, iter))) => {
+                        results.push(r);
+                        self.extend(iter);
+                        self.job_done(true);
+                    }
+                    Ok(Err(e)) => {
+                        {
+                            let Ok(mut errors) = errors.lock() else {
+                                unreachable!("Mutex should not have been poisoned");
+                            };
+                            errors.push(e);
+                        }
+                        self.job_done(false);
+                    }
+                    Err(payload) => {
+                        self.job_done(false);
+                        self.shutdown();
+                        resume_unwind(payload);
+                    }
+                }
+            }
+            results
+        };
+        let outcomes = std::thread::scope(|scope| {
+            let mut handles = Vec::with_capacity(n_threads.get());
+            for _ in 0..n_threads.get() {
+                handles.push(scope.spawn(worker));
+            }
+            handles
+                .into_iter()
+                .map(ScopedJoinHandle::join)
+                .collect::<Vec<_>>()
+        });
+        gather_results(outcomes, errors)
+    }
+
+    /// Returns the greatest number of jobs that have been waiting in the
+    /// queue at any one time
+    pub fn max_depth(&self) -> usize {
+        let Ok(data) = self.data.lock() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        data.max_depth
+    }
+
     pub fn shutdown(&self) {
         let Ok(mut data) = self.data.lock() else {
             unreachable!("Mutex should not have been poisoned");
         };
         if !data.shutdown {
-            data.jobs -= data.stack.len();
-            data.stack.clear();
+            data.jobs -= data.queue.len();
+            data.queue.clear();
             data.shutdown = true;
+            self.shutdown.store(true, atomic::Ordering::SeqCst);
             self.cond.notify_all();
+            let snapshot = self.progress.is_some().then(|| self.snapshot_of(&data));
+            drop(data);
+            self.report(snapshot);
         }
     }
 
     pub fn is_shutdown(&self) -> bool {
-        let Ok(data) = self.data.lock() else {
-            unreachable!("Mutex should not have been poisoned");
-        };
-        data.shutdown
+        self.shutdown.load(atomic::Ordering::SeqCst)
     }
 
     fn pop(&self) -> Option<T> {
@@ -105,7 +540,10 @@ impl<T> JobStack<T> {
             if data.jobs == 0 || data.shutdown {
                 return None;
             }
-            if let value @ Some(_) = data.stack.pop() {
+            if let value @ Some(_) = data.queue.pop() {
+                let snapshot = self.progress.is_some().then(|| self.snapshot_of(&data));
+                drop(data);
+                self.report(snapshot);
                 return value;
             } else {
                 let Ok(data2) = self.cond.wait(data) else {
@@ -116,26 +554,1215 @@ impl<T> JobStack<T> {
         }
     }
 
-    fn job_done(&self) {
+    fn job_done(&self, success: bool) {
         let Ok(mut data) = self.data.lock() else {
             unreachable!("Mutex should not have been poisoned");
         };
         data.jobs -= 1;
+        if success {
+            data.completed += 1;
+        } else {
+            data.failed += 1;
+        }
         if data.jobs == 0 {
             self.cond.notify_all();
         }
+        let stop = !success && self.policy.stops_after(data.failed);
+        let snapshot = self.progress.is_some().then(|| self.snapshot_of(&data));
+        drop(data);
+        self.report(snapshot);
+        if stop {
+            self.shutdown();
+        }
     }
 
     // We can't impl Extend, as that requires the receiver to be mut
     fn extend<I: IntoIterator<Item = T>>(&self, iter: I) {
+        let iter = self.unvisited(iter);
         let Ok(mut data) = self.data.lock() else {
             unreachable!("Mutex should not have been poisoned");
         };
         if !data.shutdown {
-            let prelen = data.stack.len();
-            data.stack.extend(iter);
-            data.jobs += data.stack.len() - prelen;
-            self.cond.notify_all();
+            let prelen = data.queue.len();
+            for job in iter {
+                data.queue.push(job);
+            }
+            let added = data.queue.len() - prelen;
+            if added > 0 {
+                data.jobs += added;
+                data.max_depth = data.max_depth.max(data.queue.len());
+                self.cond.notify_all();
+                let snapshot = self.progress.is_some().then(|| self.snapshot_of(&data));
+                drop(data);
+                self.report(snapshot);
+            }
+        }
+    }
+
+    fn snapshot_of(&self, data: &JobStackData<Q>) -> Snapshot {
+        let queued = data.queue.len();
+        // The counters are updated independently of one another, so the
+        // number of stolen jobs in flight can briefly appear negative.
+        let stolen_queued = self.stealing.queued.load(atomic::Ordering::SeqCst);
+        let stolen_in_flight = self.stealing.pending().saturating_sub(stolen_queued);
+        Snapshot {
+            pending: queued + stolen_queued,
+            in_flight: data.jobs - queued + stolen_in_flight,
+            completed: data.completed + self.stealing.completed.load(atomic::Ordering::SeqCst),
+            failed: data.failed + self.stealing.failed.load(atomic::Ordering::SeqCst),
+        }
+    }
+
+    /// Pass a new snapshot to the progress callback, if any.  This must not
+    /// be called while holding the lock on `data`.
+    fn report_progress(&self) {
+        if self.progress.is_some() {
+            self.report(Some(self.snapshot()));
+        }
+    }
+
+    /// Wake up any threads waiting in `wait_idle()` after the last job being
+    /// processed by `run_work_stealing()` has finished
+    fn notify_waiters(&self) {
+        drop(self.data.lock());
+        self.cond.notify_all();
+    }
+
+    /// Pass `snapshot` to the progress callback, if any.  This must not be
+    /// called while holding the lock on `data`.
+    fn report(&self, snapshot: Option<Snapshot>) {
+        if let Some((ProgressCallback(callback), snapshot)) = self.progress.as_ref().zip(snapshot) {
+            callback(snapshot);
+        }
+    }
+
+    /// If deduplication is enabled, filter out the jobs in `iter` that have
+    /// been seen before
+    fn unvisited<I: IntoIterator<Item = T>>(&self, iter: I) -> Vec<T> {
+        if let Some(visited) = &self.visited {
+            let mut visited = lock_visited(visited);
+            iter.into_iter().filter(|job| visited.check(job)).collect()
+        } else {
+            iter.into_iter().collect()
+        }
+    }
+}
+
+impl<T, Q: fmt::Debug> fmt::Debug for JobStack<T, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobStack")
+            .field("data", &self.data)
+            .field("cond", &self.cond)
+            .field("visited", &self.visited)
+            .field("progress", &self.progress)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A queue discipline for the jobs in a [`JobStack`]
+pub trait JobQueue<T> {
+    /// Add a job to the queue
+    fn push(&mut self, job: T);
+
+    /// Remove & return the next job to process, if any
+    fn pop(&mut self) -> Option<T>;
+
+    /// Returns the number of jobs in the queue
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all jobs from the queue
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Remove all jobs for which `f` returns `false` from the queue.  The
+    /// order in which `f` is called on the jobs is unspecified.
+    fn retain<P: FnMut(&T) -> bool>(&mut self, f: P);
+}
+
+/// LIFO order
+impl<T> JobQueue<T> for Vec<T> {
+    fn push(&mut self, job: T) {
+        Vec::push(self, job);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn retain<P: FnMut(&T) -> bool>(&mut self, f: P) {
+        Vec::retain(self, f);
+    }
+}
+
+/// FIFO order
+impl<T> JobQueue<T> for VecDeque<T> {
+    fn push(&mut self, job: T) {
+        self.push_back(job);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn clear(&mut self) {
+        VecDeque::clear(self);
+    }
+
+    fn retain<P: FnMut(&T) -> bool>(&mut self, f: P) {
+        VecDeque::retain(self, f);
+    }
+}
+
+/// Greatest job first
+impl<T: Ord> JobQueue<T> for BinaryHeap<T> {
+    fn push(&mut self, job: T) {
+        BinaryHeap::push(self, job);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BinaryHeap::pop(self)
+    }
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self);
+    }
+
+    fn retain<P: FnMut(&T) -> bool>(&mut self, f: P) {
+        BinaryHeap::retain(self, f);
+    }
+}
+
+/// A priority queue of jobs that pops the job with the greatest key first,
+/// where the key for each job is computed by a user-supplied function.  Jobs
+/// with equal keys are popped in the order they were pushed.
+pub struct PriorityQueue<T, K, F> {
+    heap: BinaryHeap<Prioritized<K, T>>,
+    key: F,
+    pushed: u64,
+}
+
+impl<T, K: Ord, F: Fn(&T) -> K> PriorityQueue<T, K, F> {
+    pub fn new(key: F) -> Self {
+        PriorityQueue {
+            heap: BinaryHeap::new(),
+            key,
+            pushed: 0,
+        }
+    }
+}
+
+impl<T, K: Ord, F: Fn(&T) -> K> JobQueue<T> for PriorityQueue<T, K, F> {
+    fn push(&mut self, job: T) {
+        let key = (self.key)(&job);
+        self.heap.push(Prioritized {
+            key,
+            seq: Reverse(self.pushed),
+            job,
+        });
+        self.pushed += 1;
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.heap.pop().map(|p| p.job)
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    fn retain<P: FnMut(&T) -> bool>(&mut self, mut f: P) {
+        self.heap.retain(|p| f(&p.job));
+    }
+}
+
+impl<T, K: Ord, F: Fn(&T) -> K> Extend<T> for PriorityQueue<T, K, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for job in iter {
+            self.push(job);
+        }
+    }
+}
+
+impl<T: fmt::Debug, K: fmt::Debug, F> fmt::Debug for PriorityQueue<T, K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityQueue")
+            .field("heap", &self.heap)
+            .field("pushed", &self.pushed)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Prioritized<K, T> {
+    key: K,
+    seq: Reverse<u64>,
+    job: T,
+}
+
+impl<K: Ord, T> PartialEq for Prioritized<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for Prioritized<K, T> {}
+
+impl<K: Ord, T> PartialOrd for Prioritized<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for Prioritized<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.key, self.seq).cmp(&(&other.key, other.seq))
+    }
+}
+
+/// A snapshot of the state of a [`JobStack`], as returned by
+/// [`JobStack::snapshot()`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Snapshot {
+    /// The number of jobs waiting on the stack
+    pub pending: usize,
+
+    /// The number of jobs currently being processed
+    pub in_flight: usize,
+
+    /// The number of jobs that have completed successfully
+    pub completed: usize,
+
+    /// The number of jobs that have returned `Err` or panicked
+    pub failed: usize,
+}
+
+struct ProgressCallback(Box<dyn Fn(Snapshot) + Send + Sync>);
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback(..)")
+    }
+}
+
+/// The set of jobs seen by a [`JobStack`] with deduplication enabled
+struct Visited<T> {
+    // Records the job as seen and returns `true` if it had not been seen
+    // before
+    is_new: Box<dyn FnMut(&T) -> bool + Send>,
+    skipped: usize,
+}
+
+impl<T> Visited<T> {
+    fn check(&mut self, job: &T) -> bool {
+        let new = (self.is_new)(job);
+        if !new {
+            self.skipped += 1;
+        }
+        new
+    }
+}
+
+impl<T> fmt::Debug for Visited<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Visited")
+            .field("skipped", &self.skipped)
+            .finish_non_exhaustive()
+    }
+}
+
+fn lock_visited<T>(visited: &Mutex<Visited<T>>) -> std::sync::MutexGuard<'_, Visited<T>> {
+    let Ok(guard) = visited.lock() else {
+        unreachable!("Mutex should not have been poisoned");
+    };
+    guard
+}
+
+/// The per-worker deques & bookkeeping used by
+/// [`JobStack::run_work_stealing()`]
+struct StealingPool<'s, T> {
+    deques: Vec<Mutex<VecDeque<T>>>,
+    counts: &'s StealingCounts,
+    max_depth: AtomicUsize,
+    // Held by idle workers while they check for work and then wait on
+    // `wakeup`, and by anyone notifying them, so that no wakeup is missed
+    idle: Mutex<()>,
+    wakeup: Condvar,
+}
+
+impl<T> StealingPool<'_, T> {
+    /// Take a job from the back of worker `i`'s deque or, failing that, from
+    /// the front of another worker's deque
+    fn take(&self, i: usize) -> Option<T> {
+        let n = self.deques.len();
+        // This must be a separate statement so that the lock on our own
+        // deque is released before we try to lock any others.
+        let own = lock_deque(&self.deques[i]).pop_back();
+        let job = own.or_else(|| {
+            (1..n).find_map(|offset| lock_deque(&self.deques[(i + offset) % n]).pop_front())
+        })?;
+        self.counts.queued.fetch_sub(1, atomic::Ordering::SeqCst);
+        Some(job)
+    }
+
+    /// Push new jobs onto the back of worker `i`'s deque
+    fn give(&self, i: usize, jobs: Vec<T>) {
+        if jobs.is_empty() {
+            return;
+        }
+        // The counters must be incremented before the jobs become visible
+        // to other workers so that they never underflow.
+        let added = jobs.len();
+        self.counts
+            .pending
+            .fetch_add(added, atomic::Ordering::SeqCst);
+        let queued = self
+            .counts
+            .queued
+            .fetch_add(added, atomic::Ordering::SeqCst)
+            + added;
+        self.max_depth.fetch_max(queued, atomic::Ordering::SeqCst);
+        lock_deque(&self.deques[i]).extend(jobs);
+        self.notify_idle();
+    }
+
+    /// Mark a job as finished, returning `true` if it was the last one.  If
+    /// `shutdown` is true, the stack has been shut down, and idle workers
+    /// are woken up so that they can exit.
+    fn job_done(&self, shutdown: bool) -> bool {
+        let last = self.counts.pending.fetch_sub(1, atomic::Ordering::SeqCst) == 1;
+        if last || shutdown {
+            self.notify_idle();
+        }
+        last
+    }
+
+    /// Take a job for worker `i` as with [`StealingPool::take()`], blocking
+    /// until one becomes available.  Returns `None` once all jobs have
+    /// finished or `stop()` returns true.
+    fn next_job<P: Fn() -> bool>(&self, i: usize, stop: P) -> Option<T> {
+        if stop() {
+            return None;
+        }
+        if let job @ Some(_) = self.take(i) {
+            return job;
+        }
+        let Ok(mut guard) = self.idle.lock() else {
+            unreachable!("Mutex should not have been poisoned");
+        };
+        loop {
+            if stop() {
+                return None;
+            }
+            if let job @ Some(_) = self.take(i) {
+                return job;
+            }
+            if self.counts.pending() == 0 {
+                return None;
+            }
+            let Ok(g) = self.wakeup.wait(guard) else {
+                unreachable!("Mutex should not have been poisoned");
+            };
+            guard = g;
+        }
+    }
+
+    /// Wake up all idle workers.  As this acquires `idle`, any worker that
+    /// is checking for work will either see the change that prompted this
+    /// call or be waiting on `wakeup` by the time it is notified.
+    fn notify_idle(&self) {
+        drop(self.idle.lock());
+        self.wakeup.notify_all();
+    }
+}
+
+/// Counts of the jobs being processed by [`JobStack::run_work_stealing()`]
+#[derive(Debug, Default)]
+struct StealingCounts {
+    // Number of jobs that are either queued or in progress
+    pending: AtomicUsize,
+    // Number of jobs that are queued
+    queued: AtomicUsize,
+    completed: AtomicUsize,
+    // Number of jobs that returned `Err` or panicked
+    failed: AtomicUsize,
+}
+
+impl StealingCounts {
+    fn pending(&self) -> usize {
+        self.pending.load(atomic::Ordering::SeqCst)
+    }
+}
+
+fn lock_deque<T>(deque: &Mutex<VecDeque<T>>) -> std::sync::MutexGuard<'_, VecDeque<T>> {
+    let Ok(guard) = deque.lock() else {
+        unreachable!("Mutex should not have been poisoned");
+    };
+    guard
+}
+
+/// Combine the results and errors from each worker thread, propagating the
+/// first panic, if any
+fn gather_results<R, E>(
+    outcomes: Vec<Result<Vec<R>, Box<dyn Any + Send>>>,
+    errors: Mutex<Vec<E>>,
+) -> (Vec<R>, Vec<E>) {
+    let mut results = Vec::new();
+    let mut panic = None;
+    for outcome in outcomes {
+        match outcome {
+            Ok(rs) => results.extend(rs),
+            Err(payload) => {
+                panic.get_or_insert(payload);
+            }
+        }
+    }
+    if let Some(payload) = panic {
+        resume_unwind(payload);
+    }
+    let Ok(errors) = errors.into_inner() else {
+        unreachable!("Mutex should not have been poisoned");
+    };
+    (results, errors)
+}
+
+/// A policy for how a [`JobStack`] responds to failed jobs
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ErrorPolicy {
+    /// Shut down the stack as soon as any job fails
+    #[default]
+    StopOnFirst,
+
+    /// Keep processing the remaining jobs after a failure.  If `max_errors`
+    /// is set, the stack is shut down once that many jobs have failed.
+    KeepGoing { max_errors: Option<NonZeroUsize> },
+}
+
+impl ErrorPolicy {
+    /// Returns `true` if the stack should be shut down after `failed` jobs
+    /// have failed
+    fn stops_after(self, failed: usize) -> bool {
+        match self {
+            ErrorPolicy::StopOnFirst => failed > 0,
+            ErrorPolicy::KeepGoing { max_errors } => max_errors.is_some_and(|m| failed >= m.get()),
+        }
+    }
+}
+
+/// A job that failed during a call to [`JobStack::run_collecting()`], along
+/// with the error it produced.  This is returned by the job function itself.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JobFailure<T, E> {
+    pub job: T,
+    pub error: E,
+}
+
+/// The results of a call to [`JobStack::run_collecting()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchReport<T, R, E> {
+    /// The values returned for each successful job, in the order in which
+    /// the jobs completed
+    pub results: Vec<R>,
+
+    /// The jobs that failed, in the order in which they failed
+    pub failures: Vec<JobFailure<T, E>>,
+
+    pub stats: RunStats,
+}
+
+impl<T, R, E> BatchReport<T, R, E> {
+    /// Returns `true` if no jobs failed
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The results of a successful call to [`JobStack::run()`] or
+/// [`JobStack::run_work_stealing()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RunReport<R> {
+    /// The values returned for each job, in the order in which the jobs
+    /// completed
+    pub results: Vec<R>,
+
+    pub stats: RunStats,
+}
+
+/// Statistics about a call to [`JobStack::run()`] or
+/// [`JobStack::run_work_stealing()`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RunStats {
+    /// The number of jobs that were processed
+    pub jobs_processed: usize,
+
+    /// The greatest number of jobs that were waiting on the stack at any one
+    /// time
+    pub max_depth: usize,
+
+    /// The number of duplicate jobs that were discarded, if deduplication
+    /// was enabled
+    pub duplicates_skipped: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn threads(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+
+    /// Treat each job `n` as a node in a tree whose children are
+    /// `n * 3 + 1 ..= n * 3 + 3`, stopping at `limit`
+    fn tree_children(n: u32, limit: u32) -> Vec<u32> {
+        (n * 3 + 1..=n * 3 + 3).filter(|&c| c < limit).collect()
+    }
+
+    #[test]
+    fn test_run() {
+        let stack = JobStack::new([0]);
+        let report = stack
+            .run(threads(4), |n| Ok::<_, ()>((n, tree_children(n, 1000))))
+            .unwrap();
+        let mut results = report.results;
+        results.sort_unstable();
+        assert_eq!(results, (0..1000).collect::<Vec<_>>());
+        assert_eq!(report.stats.jobs_processed, 1000);
+        assert!(report.stats.max_depth >= 3, "{:?}", report.stats);
+        assert!(report.stats.max_depth < 1000, "{:?}", report.stats);
+    }
+
+    #[test]
+    fn test_run_empty() {
+        let stack = JobStack::<u32>::new([]);
+        let report = stack.run(threads(3), |n| Ok::<_, ()>((n, []))).unwrap();
+        assert!(report.results.is_empty());
+        assert_eq!(report.stats, RunStats::default());
+    }
+
+    #[test]
+    fn test_run_error() {
+        let stack = JobStack::new([0]);
+        let processed = AtomicUsize::new(0);
+        let r = stack.run(threads(4), |n| {
+            processed.fetch_add(1, Ordering::SeqCst);
+            if n == 40 {
+                Err(format!("job {n} failed"))
+            } else {
+                Ok(((), tree_children(n, 10_000)))
+            }
+        });
+        assert_eq!(r, Err(String::from("job 40 failed")));
+        assert!(stack.is_shutdown());
+        assert!(processed.into_inner() < 10_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "job 40 panicked")]
+    fn test_run_panic() {
+        let stack = JobStack::new([0]);
+        let _ = stack.run(threads(4), |n| {
+            assert!(n != 40, "job {n} panicked");
+            Ok::<_, ()>(((), tree_children(n, 1000)))
+        });
+    }
+
+    /// Process all jobs on a single thread, returning the order in which they
+    /// were processed
+    fn traversal<Q: JobQueue<u32>>(stack: &JobStack<u32, Q>) -> Vec<u32> {
+        let mut order = Vec::new();
+        stack
+            .handle_many_jobs(|n| {
+                order.push(n);
+                Ok::<_, ()>(tree_children(n, 13))
+            })
+            .unwrap();
+        order
+    }
+
+    #[test]
+    fn test_lifo() {
+        let stack = JobStack::new([0]);
+        assert_eq!(
+            traversal(&stack),
+            [0, 3, 12, 11, 10, 2, 9, 8, 7, 1, 6, 5, 4]
+        );
+    }
+
+    #[test]
+    fn test_fifo() {
+        let stack = JobStack::with_queue(VecDeque::from([0]));
+        assert_eq!(traversal(&stack), (0..13).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_binary_heap() {
+        let stack = JobStack::with_queue(BinaryHeap::from([0]));
+        assert_eq!(
+            traversal(&stack),
+            [0, 3, 12, 11, 10, 2, 9, 8, 7, 1, 6, 5, 4]
+        );
+        let stack = JobStack::with_queue(BinaryHeap::from([Reverse(5), Reverse(1), Reverse(3)]));
+        let mut order = Vec::new();
+        stack
+            .handle_many_jobs(|Reverse(n)| {
+                order.push(n);
+                Ok::<_, ()>([])
+            })
+            .unwrap();
+        assert_eq!(order, [1, 3, 5]);
+    }
+
+    #[test]
+    fn test_priority_queue() {
+        // Prioritize even numbers, with ties processed in FIFO order
+        let mut queue = PriorityQueue::new(|&n: &u32| n % 2 == 0);
+        queue.push(0);
+        let stack = JobStack::with_queue(queue);
+        assert_eq!(
+            traversal(&stack),
+            [0, 2, 8, 1, 4, 6, 3, 10, 12, 7, 9, 5, 11]
+        );
+    }
+
+    #[test]
+    fn test_shutdown_clears_queue() {
+        let stack = JobStack::with_queue(VecDeque::from([1, 2, 3]));
+        stack.shutdown();
+        assert!(stack.is_shutdown());
+        assert_eq!(stack.handle_job(|n| Ok::<_, ()>([n])), Ok(false));
+    }
+
+    #[test]
+    fn test_run_work_stealing() {
+        let stack = JobStack::new([0]);
+        let report = stack
+            .run_work_stealing(threads(4), |n| Ok::<_, ()>((n, tree_children(n, 1000))))
+            .unwrap();
+        let mut results = report.results;
+        results.sort_unstable();
+        assert_eq!(results, (0..1000).collect::<Vec<_>>());
+        assert_eq!(report.stats.jobs_processed, 1000);
+        assert!(report.stats.max_depth >= 3, "{:?}", report.stats);
+        assert_eq!(stack.handle_job(|n| Ok::<_, ()>([n])), Ok(false));
+    }
+
+    #[test]
+    fn test_run_work_stealing_error() {
+        let stack = JobStack::new(0..8);
+        let r = stack.run_work_stealing(threads(3), |n| {
+            if n == 40 {
+                Err(format!("job {n} failed"))
+            } else {
+                Ok(((), tree_children(n, 10_000)))
+            }
+        });
+        assert_eq!(r, Err(String::from("job 40 failed")));
+        assert!(stack.is_shutdown());
+    }
+
+    #[test]
+    #[should_panic(expected = "job 40 panicked")]
+    fn test_run_work_stealing_panic() {
+        let stack = JobStack::new([0]);
+        let _ = stack.run_work_stealing(threads(4), |n| {
+            assert!(n != 40, "job {n} panicked");
+            Ok::<_, ()>(((), tree_children(n, 1000)))
+        });
+    }
+
+    /// A graph on `0..100` with cycles
+    fn graph_edges(n: u32) -> [u32; 2] {
+        [(n * 2) % 100, (n * 3 + 1) % 100]
+    }
+
+    #[test]
+    fn test_dedup() {
+        let stack = JobStack::new([0, 5, 0]).dedup();
+        assert_eq!(stack.duplicates_skipped(), 1);
+        let mut seen = Vec::new();
+        stack
+            .handle_many_jobs(|n| {
+                seen.push(n);
+                Ok::<_, ()>(graph_edges(n))
+            })
+            .unwrap();
+        let unique = seen.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), seen.len());
+        assert!(unique.contains(&0) && unique.contains(&5) && unique.contains(&1));
+        // Every job produces two new jobs, and every job other than the two
+        // initial ones is produced exactly once without being skipped.
+        assert_eq!(
+            stack.duplicates_skipped(),
+            1 + 2 * seen.len() - (seen.len() - 2)
+        );
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let stack = JobStack::with_queue(VecDeque::from([(0, 'a')])).dedup_by_key(|&(n, _)| n);
+        let mut seen = Vec::new();
+        stack
+            .handle_many_jobs(|(n, c)| {
+                seen.push((n, c));
+                Ok::<_, ()>([((n + 1) % 4, 'b'), ((n + 2) % 4, 'c')])
+            })
+            .unwrap();
+        assert_eq!(seen, [(0, 'a'), (1, 'b'), (2, 'c'), (3, 'c')]);
+        assert_eq!(stack.duplicates_skipped(), 5);
+    }
+
+    #[test]
+    fn test_dedup_borrowed() {
+        // Each name links to the names at the given indices.
+        let graph = [
+            (String::from("a"), vec![1, 2]),
+            (String::from("b"), vec![2, 0]),
+            (String::from("c"), vec![0, 1, 3]),
+            (String::from("d"), vec![3]),
+        ];
+        let stack = JobStack::new([graph[0].0.as_str()]).dedup_by_key(|&name| String::from(name));
+        let report = stack
+            .run(threads(2), |name: &str| {
+                let (_, links) = graph.iter().find(|(n, _)| n == name).unwrap();
+                Ok::<_, ()>((name, links.iter().map(|&i| graph[i].0.as_str())))
+            })
+            .unwrap();
+        let mut results = report.results;
+        results.sort_unstable();
+        assert_eq!(results, ["a", "b", "c", "d"]);
+        assert_eq!(report.stats.duplicates_skipped, 5);
+    }
+
+    #[test]
+    fn test_run_dedup() {
+        let stack = JobStack::new([0]).dedup();
+        let report = stack
+            .run(threads(4), |n| Ok::<_, ()>((n, graph_edges(n))))
+            .unwrap();
+        let unique = report.results.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), report.results.len());
+        assert_eq!(report.stats.jobs_processed, unique.len());
+        assert_eq!(
+            report.stats.duplicates_skipped,
+            unique.len() * 2 - (unique.len() - 1)
+        );
+    }
+
+    #[test]
+    fn test_run_work_stealing_dedup() {
+        let stack = JobStack::new([0]).dedup();
+        let report = stack
+            .run_work_stealing(threads(4), |n| Ok::<_, ()>((n, graph_edges(n))))
+            .unwrap();
+        let unique = report.results.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), report.results.len());
+        assert_eq!(
+            report.stats.duplicates_skipped,
+            unique.len() * 2 - (unique.len() - 1)
+        );
+    }
+
+    #[test]
+    fn test_priority_queue_retain() {
+        let mut queue = PriorityQueue::new(|&(n, _): &(u32, char)| n);
+        queue.extend([(1, 'a'), (2, 'b'), (1, 'c'), (3, 'd'), (1, 'e')]);
+        queue.retain(|&(_, c)| c != 'c');
+        assert_eq!(queue.len(), 4);
+        let popped = std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>();
+        assert_eq!(popped, [(3, 'd'), (2, 'b'), (1, 'a'), (1, 'e')]);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let stack = JobStack::new([1, 2, 3]);
+        assert_eq!(
+            stack.snapshot(),
+            Snapshot {
+                pending: 3,
+                ..Snapshot::default()
+            }
+        );
+        let r = stack.handle_job(|n| {
+            assert_eq!(
+                stack.snapshot(),
+                Snapshot {
+                    pending: 2,
+                    in_flight: 1,
+                    ..Snapshot::default()
+                }
+            );
+            Ok::<_, ()>([n * 10, n * 20])
+        });
+        assert_eq!(r, Ok(true));
+        assert_eq!(stack.handle_job(|_| Err::<[u32; 0], _>(())), Err(()));
+        assert_eq!(
+            stack.snapshot(),
+            Snapshot {
+                pending: 0,
+                in_flight: 0,
+                completed: 1,
+                failed: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_on_progress() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+        let stack = JobStack::new([0]).on_progress(move |snapshot| {
+            let _ = sender.lock().unwrap().send(snapshot);
+        });
+        stack
+            .handle_many_jobs(|n| Ok::<_, ()>(tree_children(n, 4)))
+            .unwrap();
+        drop(stack);
+        let snapshots = receiver.into_iter().collect::<Vec<_>>();
+        let snap = |pending, in_flight, completed| Snapshot {
+            pending,
+            in_flight,
+            completed,
+            failed: 0,
+        };
+        assert_eq!(
+            snapshots,
+            [
+                // Job 0 is started, adds 3 jobs, and completes
+                snap(0, 1, 0),
+                snap(3, 1, 0),
+                snap(3, 0, 1),
+                // Jobs 3, 2, and 1 are started & completed
+                snap(2, 1, 1),
+                snap(2, 0, 2),
+                snap(1, 1, 2),
+                snap(1, 0, 3),
+                snap(0, 1, 3),
+                snap(0, 0, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_progress_counts() {
+        let stack = JobStack::new(0..10);
+        let r = stack.run(threads(2), |n| if n == 5 { Err(n) } else { Ok(((), [])) });
+        assert_eq!(r, Err(5));
+        let snapshot = stack.snapshot();
+        assert_eq!(snapshot.failed, 1);
+        assert_eq!(snapshot.pending + snapshot.in_flight, 0);
+        assert!(snapshot.completed < 10, "{snapshot:?}");
+
+        let stack = JobStack::new(0..10);
+        stack
+            .run_work_stealing(threads(2), |n| Ok::<_, ()>((n, [])))
+            .unwrap();
+        assert_eq!(
+            stack.snapshot(),
+            Snapshot {
+                completed: 10,
+                ..Snapshot::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_wait_idle() {
+        let stack = JobStack::new([0]);
+        std::thread::scope(|scope| {
+            let (sender, receiver) = std::sync::mpsc::channel::<()>();
+            let stack = &stack;
+            scope.spawn(move || {
+                stack.handle_many_jobs(|_| {
+                    // Block until the main thread says to continue
+                    let _ = receiver.recv();
+                    Ok::<_, ()>([])
+                })
+            });
+            assert!(!stack.wait_idle(Duration::from_millis(50)));
+            drop(sender);
+            assert!(stack.wait_idle(Duration::from_secs(10)));
+        });
+        assert!(stack.wait_idle(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_wait_idle_work_stealing() {
+        let stack = JobStack::new([0]);
+        std::thread::scope(|scope| {
+            let (sender, receiver) = std::sync::mpsc::channel::<()>();
+            let receiver = Mutex::new(receiver);
+            let stack = &stack;
+            let runner = scope.spawn(move || {
+                stack.run_work_stealing(threads(2), |n| {
+                    if n == 0 {
+                        // Block until the main thread says to continue
+                        let _ = receiver.lock().unwrap().recv();
+                    }
+                    Ok::<_, ()>((n, tree_children(n, 40)))
+                })
+            });
+            assert!(!stack.wait_idle(Duration::from_millis(50)));
+            let snapshot = stack.snapshot();
+            assert_eq!(snapshot.pending + snapshot.in_flight, 1, "{snapshot:?}");
+            drop(sender);
+            assert!(stack.wait_idle(Duration::from_secs(10)));
+            assert_eq!(stack.snapshot().completed, 40);
+            let report = runner.join().unwrap().unwrap();
+            assert_eq!(report.stats.jobs_processed, 40);
+        });
+    }
+
+    #[test]
+    fn test_run_work_stealing_progress() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+        let stack = JobStack::new([0]).on_progress(move |snapshot| {
+            let _ = sender.lock().unwrap().send(snapshot);
+        });
+        stack
+            .run_work_stealing(threads(1), |n| Ok::<_, ()>(((), tree_children(n, 4))))
+            .unwrap();
+        drop(stack);
+        let snapshots = receiver.into_iter().collect::<Vec<_>>();
+        let snap = |pending, in_flight, completed| Snapshot {
+            pending,
+            in_flight,
+            completed,
+            failed: 0,
+        };
+        assert_eq!(
+            snapshots,
+            [
+                snap(0, 1, 0),
+                snap(3, 1, 0),
+                snap(3, 0, 1),
+                snap(2, 1, 1),
+                snap(2, 0, 2),
+                snap(1, 1, 2),
+                snap(1, 0, 3),
+                snap(0, 1, 3),
+                snap(0, 0, 4),
+                // Final report once the run is over
+                snap(0, 0, 4),
+            ]
+        );
+    }
+
+    fn keep_going(max_errors: Option<usize>) -> ErrorPolicy {
+        ErrorPolicy::KeepGoing {
+            max_errors: max_errors.map(threads),
+        }
+    }
+
+    /// A job function for a tree of 1000 jobs in which every leaf job `n`
+    /// from 500 on that is a multiple of 50 fails
+    fn failing_leaves(n: u32) -> Result<(u32, Vec<u32>), String> {
+        if n >= 500 && n.is_multiple_of(50) {
+            Err(format!("job {n} failed"))
+        } else {
+            Ok((n, tree_children(n, 1000)))
         }
     }
+
+    fn failing_leaves_collecting(n: u32) -> Result<(u32, Vec<u32>), JobFailure<u32, String>> {
+        failing_leaves(n).map_err(|error| JobFailure { job: n, error })
+    }
+
+    #[test]
+    fn test_handle_job_keep_going() {
+        let stack = JobStack::new(0..10).error_policy(keep_going(None));
+        let mut errors = Vec::new();
+        loop {
+            match stack.handle_job(|n| if n % 2 == 1 { Err(n) } else { Ok([]) }) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(n) => errors.push(n),
+            }
+        }
+        assert_eq!(errors, [9, 7, 5, 3, 1]);
+        assert!(!stack.is_shutdown());
+        let snapshot = stack.snapshot();
+        assert_eq!(snapshot.completed, 5);
+        assert_eq!(snapshot.failed, 5);
+    }
+
+    #[test]
+    fn test_handle_many_jobs_max_errors() {
+        let stack = JobStack::new(0..10).error_policy(keep_going(Some(2)));
+        let r = stack.handle_many_jobs(|n| if n % 2 == 1 { Err(n) } else { Ok([]) });
+        assert_eq!(r, Err(9));
+        assert!(!stack.is_shutdown());
+        let r = stack.handle_many_jobs(|n| if n % 2 == 1 { Err(n) } else { Ok([]) });
+        assert_eq!(r, Err(7));
+        assert!(stack.is_shutdown());
+        assert_eq!(stack.snapshot().pending, 0);
+    }
+
+    #[test]
+    fn test_run_collecting_keep_going() {
+        let stack = JobStack::new([0]).error_policy(keep_going(None));
+        let report = stack.run_collecting(threads(4), failing_leaves_collecting);
+        assert!(!report.is_success());
+        let mut failed = report
+            .failures
+            .iter()
+            .map(|failure| {
+                assert_eq!(failure.error, format!("job {} failed", failure.job));
+                failure.job
+            })
+            .collect::<Vec<_>>();
+        failed.sort_unstable();
+        assert_eq!(failed, (500..1000).step_by(50).collect::<Vec<_>>());
+        let mut results = report.results;
+        results.sort_unstable();
+        assert_eq!(
+            results,
+            (0..1000)
+                .filter(|n| !failed.contains(n))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(report.stats.jobs_processed, 1000);
+        assert!(!stack.is_shutdown());
+        assert_eq!(stack.snapshot().failed, 10);
+    }
+
+    #[test]
+    fn test_run_collecting_max_errors() {
+        let stack = JobStack::new([0]).error_policy(keep_going(Some(3)));
+        let report = stack.run_collecting(threads(4), failing_leaves_collecting);
+        // Jobs that were already in flight when the third failure occurred
+        // may fail as well.
+        assert!(
+            (3..=6).contains(&report.failures.len()),
+            "{:?}",
+            report.failures
+        );
+        assert!(report.stats.jobs_processed < 1000, "{:?}", report.stats);
+        assert!(stack.is_shutdown());
+    }
+
+    #[test]
+    fn test_run_collecting_stop_on_first() {
+        let stack = JobStack::new([0]);
+        let report = stack.run_collecting(threads(4), failing_leaves_collecting);
+        assert!(
+            (1..=4).contains(&report.failures.len()),
+            "{:?}",
+            report.failures
+        );
+        assert!(stack.is_shutdown());
+    }
+
+    #[test]
+    fn test_run_collecting_success() {
+        let stack = JobStack::new([0]);
+        let report = stack.run_collecting(threads(4), |n| {
+            Ok::<_, JobFailure<_, ()>>((n, tree_children(n, 100)))
+        });
+        assert!(report.is_success());
+        assert_eq!(report.results.len(), 100);
+        assert_eq!(report.stats.jobs_processed, 100);
+    }
+
+    #[test]
+    fn test_run_collecting_non_clone() {
+        // A job type that can't be cloned
+        #[derive(Debug, Eq, PartialEq)]
+        struct Job(u32);
+
+        let stack = JobStack::new([Job(0)]).error_policy(keep_going(None));
+        let report = stack.run_collecting(threads(2), |Job(n)| {
+            if n % 2 == 1 {
+                Err(JobFailure {
+                    job: Job(n),
+                    error: "odd",
+                })
+            } else {
+                Ok((
+                    n,
+                    (n < 5)
+                        .then(|| [Job(n + 1), Job(n + 2)])
+                        .into_iter()
+                        .flatten(),
+                ))
+            }
+        });
+        let mut failed = report
+            .failures
+            .into_iter()
+            .map(|failure| failure.job.0)
+            .collect::<Vec<_>>();
+        failed.sort_unstable();
+        assert_eq!(failed, [1, 3, 5]);
+        let mut results = report.results;
+        results.sort_unstable();
+        assert_eq!(results, [0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn test_run_keep_going() {
+        let stack = JobStack::new([0]).error_policy(keep_going(None));
+        let processed = AtomicUsize::new(0);
+        let r = stack.run(threads(4), |n| {
+            processed.fetch_add(1, Ordering::SeqCst);
+            failing_leaves(n)
+        });
+        assert!(r.is_err());
+        assert_eq!(processed.into_inner(), 1000);
+        assert!(!stack.is_shutdown());
+    }
+
+    #[test]
+    fn test_run_work_stealing_keep_going() {
+        let stack = JobStack::new([0]).error_policy(keep_going(None));
+        let processed = AtomicUsize::new(0);
+        let r = stack.run_work_stealing(threads(4), |n| {
+            processed.fetch_add(1, Ordering::SeqCst);
+            failing_leaves(n)
+        });
+        assert!(r.is_err());
+        assert_eq!(processed.into_inner(), 1000);
+        assert!(!stack.is_shutdown());
+        assert_eq!(stack.snapshot().failed, 10);
+    }
+
+    #[test]
+    fn test_run_work_stealing_max_errors() {
+        let stack = JobStack::new([0]).error_policy(keep_going(Some(2)));
+        let processed = AtomicUsize::new(0);
+        let r = stack.run_work_stealing(threads(4), |n| {
+            processed.fetch_add(1, Ordering::SeqCst);
+            failing_leaves(n)
+        });
+        assert!(r.is_err());
+        assert!(processed.into_inner() < 1000);
+        assert!(stack.is_shutdown());
+    }
 }
@@ -0,0 +1,193 @@
+use crate::predicate_bsearch::last_in_range;
+use std::iter::FusedIterator;
+
+/// Returns an iterator over all pairs `(i, j)` where `0 <= i < j < n`, in
+/// lexicographic order — that is, the strict upper triangle of
+/// [`cross_upto(n, n)`](crate::cross_upto::cross_upto).
+///
+/// Each pair's position in the output can be computed with
+/// [`PairsUpto::rank()`], and [`Iterator::nth()`] skips ahead in `O(log n)`
+/// time, so an interrupted traversal can be resumed from a saved index.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::pairs_upto::pairs_upto;
+/// let pairs = pairs_upto(4).collect::<Vec<_>>();
+/// assert_eq!(pairs, [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]);
+///
+/// // Resume after the first three pairs:
+/// let rest = pairs_upto(4).skip(3).collect::<Vec<_>>();
+/// assert_eq!(rest, [(1, 2), (1, 3), (2, 3)]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the number of pairs exceeds `usize::MAX`
+pub fn pairs_upto(n: usize) -> PairsUpto {
+    let len = if n.is_multiple_of(2) {
+        (n / 2).checked_mul(n.saturating_sub(1))
+    } else {
+        n.checked_mul(n / 2)
+    }
+    .expect("number of pairs should not exceed usize::MAX");
+    PairsUpto {
+        n,
+        len,
+        pos: 0,
+        next: (0, 1),
+    }
+}
+
+/// Iterator returned by [`pairs_upto()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairsUpto {
+    n: usize,
+    len: usize,
+    pos: usize,
+    next: (usize, usize),
+}
+
+impl PairsUpto {
+    /// Returns the number of pairs that have been yielded so far, which is
+    /// also the rank of the next pair to be yielded
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the index of `(i, j)` in the complete output of this
+    /// iterator, or `None` if the pair is not part of the output
+    pub fn rank(&self, (i, j): (usize, usize)) -> Option<usize> {
+        (i < j && j < self.n).then(|| self.row_start(i) + (j - i - 1))
+    }
+
+    /// Returns the pair at index `index` in the complete output of this
+    /// iterator, or `None` if `index` is out of range
+    pub fn unrank(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        let i = last_in_range(0..self.n - 1, |i| self.row_start(i) <= index)?;
+        Some((i, index - self.row_start(i) + i + 1))
+    }
+
+    /// Returns the rank of `(i, i + 1)`, the first pair with first element
+    /// `i`
+    fn row_start(&self, i: usize) -> usize {
+        // i * (2n - i - 1) / 2, arranged to avoid intermediate overflow
+        let width = self.n - i - 1 + self.n;
+        if i.is_multiple_of(2) {
+            (i / 2) * width
+        } else {
+            i * (width / 2)
+        }
+    }
+}
+
+impl Iterator for PairsUpto {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.pos >= self.len {
+            return None;
+        }
+        let p = self.next;
+        self.pos += 1;
+        self.next = if p.1 + 1 < self.n {
+            (p.0, p.1 + 1)
+        } else {
+            (p.0 + 1, p.0 + 2)
+        };
+        Some(p)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<(usize, usize)> {
+        if let Some(p) = self.pos.checked_add(n).and_then(|i| self.unrank(i)) {
+            self.pos += n;
+            self.next = p;
+            self.next()
+        } else {
+            self.pos = self.len;
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sz = self.len - self.pos;
+        (sz, Some(sz))
+    }
+}
+
+impl FusedIterator for PairsUpto {}
+
+impl ExactSizeIterator for PairsUpto {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross_upto::cross_upto;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_small() {
+        for n in 0..10 {
+            let expected = cross_upto(n, n).filter(|&(i, j)| i < j).collect::<Vec<_>>();
+            let mut iter = pairs_upto(n);
+            assert_eq!(iter.len(), expected.len());
+            for (r, &p) in expected.iter().enumerate() {
+                assert_eq!(iter.position(), r);
+                assert_eq!(iter.rank(p), Some(r));
+                assert_eq!(iter.unrank(r), Some(p));
+                assert_eq!(iter.next(), Some(p));
+                assert_eq!(iter.len(), expected.len() - r - 1);
+            }
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.unrank(expected.len()), None);
+        }
+    }
+
+    #[test]
+    fn test_rank_invalid() {
+        let iter = pairs_upto(5);
+        assert_eq!(iter.rank((2, 2)), None);
+        assert_eq!(iter.rank((3, 2)), None);
+        assert_eq!(iter.rank((2, 5)), None);
+    }
+
+    #[test]
+    fn test_large() {
+        let n = 1 << 32;
+        let mut iter = pairs_upto(n);
+        assert_eq!(iter.len(), (n / 2) * (n - 1));
+        let last = iter.len() - 1;
+        assert_eq!(iter.unrank(last), Some((n - 2, n - 1)));
+        assert_eq!(iter.rank((n - 2, n - 1)), Some(last));
+        assert_eq!(iter.nth(n - 1), Some((1, 2)));
+        assert_eq!(iter.position(), n);
+    }
+
+    #[test]
+    #[should_panic(expected = "number of pairs should not exceed usize::MAX")]
+    fn test_overflow() {
+        let _ = pairs_upto(usize::MAX);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_nth(n in 0usize..30, skip1 in 0usize..50, skip2 in 0usize..50) {
+            let expected = pairs_upto(n).collect::<Vec<_>>();
+            let mut iter = pairs_upto(n);
+            prop_assert_eq!(iter.nth(skip1), expected.get(skip1).copied());
+            prop_assert_eq!(iter.nth(skip2), expected.get(skip1 + skip2 + 1).copied());
+            prop_assert_eq!(iter.len(), expected.len().saturating_sub(skip1 + skip2 + 2));
+        }
+
+        #[test]
+        fn prop_rank_unrank(n in 2usize..1_000_000, r in any::<prop::sample::Index>()) {
+            let iter = pairs_upto(n);
+            let r = r.index(iter.len());
+            let p = iter.unrank(r).unwrap();
+            prop_assert_eq!(iter.rank(p), Some(r));
+        }
+    }
+}
@@ -0,0 +1,670 @@
+//! Parsing [`Duration`]s from strings in the formats produced by
+//! [`show_duration`](crate::show_duration)
+//!
+//! All of the parsers are exact: fractional values are computed with integer
+//! arithmetic, and a value with a nonzero component smaller than a nanosecond
+//! is an error rather than being rounded.  Leading & trailing whitespace is
+//! not accepted.
+use ParseDurationErrorKind::*;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+const NANOS_PER_MIN: u128 = 60 * NANOS_PER_SEC;
+const NANOS_PER_HOUR: u128 = 60 * NANOS_PER_MIN;
+const NANOS_PER_DAY: u128 = 24 * NANOS_PER_HOUR;
+const NANOS_PER_WEEK: u128 = 7 * NANOS_PER_DAY;
+const MAX_NANOS: u128 = (u64::MAX as u128) * NANOS_PER_SEC + (NANOS_PER_SEC - 1);
+
+/// Parse a duration in any of the formats accepted by the other functions in
+/// this module.  The format is determined as follows:
+///
+/// - If the string starts with `P`, it is parsed with [`parse_iso8601()`].
+/// - Otherwise, if the string contains a colon, it is parsed with
+///   [`parse_clock()`].
+/// - Otherwise, if the string consists only of digits and periods, it is
+///   parsed with [`parse_seconds()`].
+/// - Otherwise, the string is parsed with [`parse_units()`].
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::parse_duration::parse_duration;
+/// # use std::time::Duration;
+/// let d = Duration::from_millis(5_400_250);
+/// assert_eq!(parse_duration("5400.25"), Ok(d));
+/// assert_eq!(parse_duration("1h30m0.25s"), Ok(d));
+/// assert_eq!(parse_duration("PT1H30M0.25S"), Ok(d));
+/// assert_eq!(parse_duration("01:30:00.250"), Ok(d));
+/// ```
+pub fn parse_duration(s: &str) -> Result<Duration, ParseDurationError> {
+    if s.starts_with('P') {
+        parse_iso8601(s)
+    } else if s.contains(':') {
+        parse_clock(s)
+    } else if s.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        parse_seconds(s)
+    } else {
+        parse_units(s)
+    }
+}
+
+/// Parse a decimal number of seconds of the form `digits[.digits]`, as
+/// produced by [`DurationExt::display_as_seconds()`][1].
+///
+/// [1]: crate::show_duration::DurationExt::display_as_seconds
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::parse_duration::parse_seconds;
+/// # use std::time::Duration;
+/// assert_eq!(parse_seconds("42"), Ok(Duration::from_secs(42)));
+/// assert_eq!(parse_seconds("0.000000001"), Ok(Duration::from_nanos(1)));
+/// assert!(parse_seconds("0.0000000001").is_err());
+/// ```
+pub fn parse_seconds(s: &str) -> Result<Duration, ParseDurationError> {
+    let mut scanner = Scanner::new(s)?;
+    let num = scanner.number(&['.'])?;
+    scanner.expect_end()?;
+    let nanos = num.to_nanos(NANOS_PER_SEC)?;
+    to_duration(nanos, num.start)
+}
+
+/// Parse a sequence of numbers with unit suffixes, e.g., `"1h30m"`,
+/// `"250ms"`, or `"2d 3.5h"`, as produced by
+/// [`DurationExt::display_humanized()`][1] and
+/// [`DurationExt::display_go()`][2].  The values of the components are
+/// summed.  Components may appear in any order and may optionally be
+/// separated by whitespace.  The string `"0"` is also accepted.
+///
+/// The recognized units are `d` (days of 24 hours), `h`, `m`, `s`, `ms`,
+/// `us` or `µs`, and `ns`.  Each component may have a fractional part as long
+/// as the result is a whole number of nanoseconds.
+///
+/// [1]: crate::show_duration::DurationExt::display_humanized
+/// [2]: crate::show_duration::DurationExt::display_go
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::parse_duration::parse_units;
+/// # use std::time::Duration;
+/// assert_eq!(parse_units("1h30m"), Ok(Duration::from_secs(5400)));
+/// assert_eq!(parse_units("1.5h"), Ok(Duration::from_secs(5400)));
+/// assert_eq!(parse_units("250ms"), Ok(Duration::from_millis(250)));
+/// assert_eq!(parse_units("1d 2h"), Ok(Duration::from_secs(93600)));
+/// ```
+pub fn parse_units(s: &str) -> Result<Duration, ParseDurationError> {
+    if s == "0" {
+        return Ok(Duration::ZERO);
+    }
+    let mut scanner = Scanner::new(s)?;
+    let mut total = 0;
+    loop {
+        let num = scanner.number(&['.'])?;
+        let unit_start = scanner.pos;
+        let unit = match scanner.take_while(char::is_alphabetic) {
+            "" => return Err(ParseDurationError::new(MissingUnit, unit_start)),
+            "d" => NANOS_PER_DAY,
+            "h" => NANOS_PER_HOUR,
+            "m" => NANOS_PER_MIN,
+            "s" => NANOS_PER_SEC,
+            "ms" => 1_000_000,
+            // U+00B5 MICRO SIGN and U+03BC GREEK SMALL LETTER MU
+            "us" | "\u{B5}s" | "\u{3BC}s" => 1_000,
+            "ns" => 1,
+            _ => return Err(ParseDurationError::new(UnknownUnit, unit_start)),
+        };
+        total = add_nanos(total, num.to_nanos(unit)?, num.start)?;
+        if scanner.is_done() {
+            break;
+        }
+        scanner.take_while(char::is_whitespace);
+    }
+    to_duration(total, 0)
+}
+
+/// Parse an [ISO 8601 duration][1] of the form `PnWnDTnHnMnS`, as produced
+/// by [`DurationExt::display_iso8601()`][2].  Each component is optional,
+/// but at least one must be present, and `T` must be omitted if there are no
+/// hour, minute, or second components.  Either `.` or `,` may be used as the
+/// decimal separator.
+///
+/// Days are treated as 24 hours and weeks as 7 days.  Years & months are
+/// rejected with [`ParseDurationErrorKind::NominalUnit`], as they do not have
+/// fixed lengths.
+///
+/// [1]: https://en.wikipedia.org/wiki/ISO_8601#Durations
+/// [2]: crate::show_duration::DurationExt::display_iso8601
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::parse_duration::parse_iso8601;
+/// # use std::time::Duration;
+/// assert_eq!(parse_iso8601("PT1H2M3.4S"), Ok(Duration::from_millis(3_723_400)));
+/// assert_eq!(parse_iso8601("P1DT12H"), Ok(Duration::from_secs(129_600)));
+/// assert!(parse_iso8601("P1M").is_err());
+/// ```
+pub fn parse_iso8601(s: &str) -> Result<Duration, ParseDurationError> {
+    let mut scanner = Scanner::new(s)?;
+    if !scanner.eat('P') {
+        return Err(scanner.unexpected());
+    }
+    let date_units = [('W', NANOS_PER_WEEK), ('D', NANOS_PER_DAY)];
+    let (mut total, mut any) = scanner.designated(&date_units, &['Y', 'M'], 0)?;
+    if scanner.eat('T') {
+        let time_units = [
+            ('H', NANOS_PER_HOUR),
+            ('M', NANOS_PER_MIN),
+            ('S', NANOS_PER_SEC),
+        ];
+        let (t, time_any) = scanner.designated(&time_units, &[], total)?;
+        if !time_any {
+            return Err(scanner.unexpected());
+        }
+        (total, any) = (t, true);
+    }
+    if !any {
+        return Err(scanner.unexpected());
+    }
+    scanner.expect_end()?;
+    to_duration(total, 0)
+}
+
+/// Parse a clock-style duration of the form `H:MM:SS[.fff]` or
+/// `M:SS[.fff]`, as produced by [`DurationExt::display_clock()`][1].  The
+/// leading field may have any number of digits, while the remaining fields
+/// must have exactly two digits each and be less than 60.
+///
+/// [1]: crate::show_duration::DurationExt::display_clock
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::parse_duration::parse_clock;
+/// # use std::time::Duration;
+/// assert_eq!(parse_clock("01:02:03.400"), Ok(Duration::from_millis(3_723_400)));
+/// assert_eq!(parse_clock("100:00:00"), Ok(Duration::from_secs(360_000)));
+/// assert_eq!(parse_clock("2:30"), Ok(Duration::from_secs(150)));
+/// assert!(parse_clock("01:60:00").is_err());
+/// ```
+pub fn parse_clock(s: &str) -> Result<Duration, ParseDurationError> {
+    let mut scanner = Scanner::new(s)?;
+    let lead = scanner.number(&[])?;
+    if !scanner.eat(':') {
+        return Err(scanner.unexpected());
+    }
+    let middle = scanner.clock_field(&[])?;
+    let (hours, minutes, seconds) = if scanner.eat(':') {
+        let seconds = scanner.clock_field(&['.'])?;
+        (Some(lead), middle, seconds)
+    } else if scanner.eat('.') {
+        // The seconds field of `M:SS` is the only one that can have a
+        // fractional part.
+        let mut seconds = middle;
+        seconds.frac_start = scanner.pos;
+        seconds.frac = scanner.take_while(|c| c.is_ascii_digit());
+        if seconds.frac.is_empty() {
+            return Err(scanner.unexpected());
+        }
+        (None, lead, seconds)
+    } else {
+        (None, lead, middle)
+    };
+    scanner.expect_end()?;
+    let mut total = seconds.to_nanos(NANOS_PER_SEC)?;
+    total = add_nanos(total, minutes.to_nanos(NANOS_PER_MIN)?, minutes.start)?;
+    if let Some(hours) = hours {
+        total = add_nanos(total, hours.to_nanos(NANOS_PER_HOUR)?, hours.start)?;
+    }
+    to_duration(total, 0)
+}
+
+/// Error returned when parsing a duration fails
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParseDurationError {
+    kind: ParseDurationErrorKind,
+    position: usize,
+}
+
+impl ParseDurationError {
+    fn new(kind: ParseDurationErrorKind, position: usize) -> ParseDurationError {
+        ParseDurationError { kind, position }
+    }
+
+    /// Returns the kind of error that occurred
+    pub fn kind(&self) -> ParseDurationErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte offset in the input string at which the error was
+    /// detected
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+
+impl Error for ParseDurationError {}
+
+/// The kinds of errors that can occur when parsing a duration
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParseDurationErrorKind {
+    /// The input string was empty
+    Empty,
+    /// An unexpected character was encountered
+    UnexpectedChar(char),
+    /// The input ended before a complete duration was parsed
+    UnexpectedEnd,
+    /// A number was not followed by a unit
+    MissingUnit,
+    /// A number was followed by an unrecognized unit
+    UnknownUnit,
+    /// An ISO 8601 duration contained years or months, which do not have
+    /// fixed lengths
+    NominalUnit,
+    /// A minutes or seconds field in a clock-style duration was 60 or greater
+    FieldOutOfRange,
+    /// A value had a nonzero component smaller than a nanosecond
+    ExcessPrecision,
+    /// The duration was too large to be represented by a [`Duration`]
+    OutOfRange,
+}
+
+impl fmt::Display for ParseDurationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Empty => write!(f, "empty duration string"),
+            UnexpectedChar(c) => write!(f, "unexpected character {c:?}"),
+            UnexpectedEnd => write!(f, "unexpected end of input"),
+            MissingUnit => write!(f, "number is missing a unit"),
+            UnknownUnit => write!(f, "unknown unit"),
+            NominalUnit => write!(f, "years and months do not have fixed lengths"),
+            FieldOutOfRange => write!(f, "clock field must be less than 60"),
+            ExcessPrecision => write!(f, "value is more precise than a nanosecond"),
+            OutOfRange => write!(f, "duration is too large"),
+        }
+    }
+}
+
+/// A nonnegative decimal number in the input string
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Number<'a> {
+    /// Byte offset of the start of the number
+    start: usize,
+    int: &'a str,
+    frac: &'a str,
+    /// Byte offset of the start of `frac`
+    frac_start: usize,
+}
+
+impl Number<'_> {
+    /// Return the number of nanoseconds in this many units of `unit`
+    /// nanoseconds
+    fn to_nanos(self, unit: u128) -> Result<u128, ParseDurationError> {
+        let out_of_range = ParseDurationError::new(OutOfRange, self.start);
+        let mut total = self
+            .int
+            .bytes()
+            .try_fold(0u128, |acc, b| {
+                acc.checked_mul(10)?.checked_add(u128::from(b - b'0'))
+            })
+            .and_then(|int| int.checked_mul(unit))
+            .ok_or(out_of_range)?;
+        let frac = self.frac.trim_end_matches('0');
+        if !frac.is_empty() {
+            // The last nonzero digit is the one that makes the value too
+            // precise, if anything does.
+            let excess = ParseDurationError::new(ExcessPrecision, self.frac_start + frac.len() - 1);
+            let (numer, denom) = frac
+                .bytes()
+                .try_fold((0u128, 1u128), |(n, d), b| {
+                    Some((
+                        n.checked_mul(10)?.checked_add(u128::from(b - b'0'))?,
+                        d.checked_mul(10)?,
+                    ))
+                })
+                .ok_or(excess)?;
+            let scaled = numer.checked_mul(unit).ok_or(excess)?;
+            if scaled % denom != 0 {
+                return Err(excess);
+            }
+            total = total.checked_add(scaled / denom).ok_or(out_of_range)?;
+        }
+        Ok(total)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Scanner<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(s: &'a str) -> Result<Scanner<'a>, ParseDurationError> {
+        if s.is_empty() {
+            Err(ParseDurationError::new(Empty, 0))
+        } else {
+            Ok(Scanner { s, pos: 0 })
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.s.len()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while<P: FnMut(char) -> bool>(&mut self, mut predicate: P) -> &'a str {
+        let start = self.pos;
+        while let Some(ch) = self.peek().filter(|&c| predicate(c)) {
+            self.pos += ch.len_utf8();
+        }
+        &self.s[start..self.pos]
+    }
+
+    /// Returns an error for whatever is at the current position
+    fn unexpected(&self) -> ParseDurationError {
+        let kind = match self.peek() {
+            Some(ch) => UnexpectedChar(ch),
+            None => UnexpectedEnd,
+        };
+        ParseDurationError::new(kind, self.pos)
+    }
+
+    fn expect_end(&self) -> Result<(), ParseDurationError> {
+        if self.is_done() {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Parse a number of the form `digits[<sep>digits]`, where `<sep>` is one
+    /// of the characters in `separators`
+    fn number(&mut self, separators: &[char]) -> Result<Number<'a>, ParseDurationError> {
+        let start = self.pos;
+        let int = self.take_while(|c| c.is_ascii_digit());
+        if int.is_empty() {
+            return Err(self.unexpected());
+        }
+        let mut num = Number {
+            start,
+            int,
+            frac: "",
+            frac_start: self.pos,
+        };
+        if separators.iter().any(|&sep| self.eat(sep)) {
+            num.frac_start = self.pos;
+            num.frac = self.take_while(|c| c.is_ascii_digit());
+            if num.frac.is_empty() {
+                return Err(self.unexpected());
+            }
+        }
+        Ok(num)
+    }
+
+    /// Parse a two-digit clock field whose value must be less than 60,
+    /// optionally followed by a fractional part
+    fn clock_field(&mut self, separators: &[char]) -> Result<Number<'a>, ParseDurationError> {
+        let start = self.pos;
+        for _ in 0..2 {
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.unexpected());
+            }
+            self.pos += 1;
+        }
+        if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Err(self.unexpected());
+        }
+        if self.s.as_bytes()[start] >= b'6' {
+            return Err(ParseDurationError::new(FieldOutOfRange, start));
+        }
+        self.pos = start;
+        self.number(separators)
+    }
+
+    /// Parse a sequence of ISO 8601 components, each consisting of a number
+    /// followed by a designator.  The designators in `units` must appear in
+    /// order, and each at most once.  The designators in `nominal` are
+    /// rejected with `NominalUnit`.  Returns the sum of the components &
+    /// `total` along with whether any components were parsed.
+    fn designated(
+        &mut self,
+        units: &[(char, u128)],
+        nominal: &[char],
+        mut total: u128,
+    ) -> Result<(u128, bool), ParseDurationError> {
+        let mut next_unit = 0;
+        let mut any = false;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            let num = self.number(&['.', ','])?;
+            let Some(designator) = self.peek() else {
+                return Err(ParseDurationError::new(MissingUnit, self.pos));
+            };
+            if nominal.contains(&designator) {
+                return Err(ParseDurationError::new(NominalUnit, self.pos));
+            }
+            let Some(i) = units[next_unit..]
+                .iter()
+                .position(|&(d, _)| d == designator)
+            else {
+                return Err(self.unexpected());
+            };
+            next_unit += i + 1;
+            self.pos += designator.len_utf8();
+            total = add_nanos(total, num.to_nanos(units[next_unit - 1].1)?, num.start)?;
+            any = true;
+        }
+        Ok((total, any))
+    }
+}
+
+/// Add two nanosecond counts, failing if the result is too large for a
+/// `Duration`.  `position` is the location of the component being added.
+fn add_nanos(total: u128, nanos: u128, position: usize) -> Result<u128, ParseDurationError> {
+    total
+        .checked_add(nanos)
+        .filter(|&n| n <= MAX_NANOS)
+        .ok_or_else(|| ParseDurationError::new(OutOfRange, position))
+}
+
+fn to_duration(nanos: u128, position: usize) -> Result<Duration, ParseDurationError> {
+    let out_of_range = ParseDurationError::new(OutOfRange, position);
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).map_err(|_| out_of_range)?;
+    let subsec = u32::try_from(nanos % NANOS_PER_SEC).map_err(|_| out_of_range)?;
+    Ok(Duration::new(secs, subsec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::show_duration::DurationExt;
+    use proptest::prelude::*;
+    use rstest::rstest;
+
+    fn err(kind: ParseDurationErrorKind, position: usize) -> Result<Duration, ParseDurationError> {
+        Err(ParseDurationError::new(kind, position))
+    }
+
+    #[rstest]
+    #[case("0", Ok(Duration::ZERO))]
+    #[case("42", Ok(Duration::from_secs(42)))]
+    #[case("0.123", Ok(Duration::from_millis(123)))]
+    #[case("10.123456789", Ok(Duration::new(10, 123_456_789)))]
+    #[case("1.500000000000", Ok(Duration::from_millis(1500)))]
+    #[case("18446744073709551615.999999999", Ok(Duration::MAX))]
+    #[case("", err(Empty, 0))]
+    #[case(".5", err(UnexpectedChar('.'), 0))]
+    #[case("5.", err(UnexpectedEnd, 2))]
+    #[case("1.2.3", err(UnexpectedChar('.'), 3))]
+    #[case(" 1", err(UnexpectedChar(' '), 0))]
+    #[case("1 ", err(UnexpectedChar(' '), 1))]
+    #[case("-1", err(UnexpectedChar('-'), 0))]
+    #[case("0.0000000001", err(ExcessPrecision, 11))]
+    #[case("0.1234567891000", err(ExcessPrecision, 11))]
+    #[case("18446744073709551616", err(OutOfRange, 0))]
+    fn test_parse_seconds(#[case] s: &str, #[case] r: Result<Duration, ParseDurationError>) {
+        assert_eq!(parse_seconds(s), r);
+    }
+
+    #[rstest]
+    #[case("0", Ok(Duration::ZERO))]
+    #[case("0s", Ok(Duration::ZERO))]
+    #[case("1h30m", Ok(Duration::from_secs(5400)))]
+    #[case("1.5h", Ok(Duration::from_secs(5400)))]
+    #[case("250ms", Ok(Duration::from_millis(250)))]
+    #[case("2d", Ok(Duration::from_secs(172_800)))]
+    #[case("1d 2h 3m 4.5s", Ok(Duration::from_millis(93_784_500)))]
+    #[case("1us", Ok(Duration::from_micros(1)))]
+    #[case("1µs", Ok(Duration::from_micros(1)))]
+    #[case("1μs", Ok(Duration::from_micros(1)))]
+    #[case("1.5ns", err(ExcessPrecision, 2))]
+    #[case("1s1s", Ok(Duration::from_secs(2)))]
+    #[case("10", err(MissingUnit, 2))]
+    #[case("10 s", err(MissingUnit, 2))]
+    #[case("1h ", err(UnexpectedEnd, 3))]
+    #[case("5x", err(UnknownUnit, 1))]
+    #[case("5sec", err(UnknownUnit, 1))]
+    #[case("h", err(UnexpectedChar('h'), 0))]
+    #[case("1h-5m", err(UnexpectedChar('-'), 2))]
+    #[case(
+        "213503982334601d",
+        Ok(Duration::from_secs(18_446_744_073_709_526_400))
+    )]
+    #[case("213503982334602d", err(OutOfRange, 0))]
+    #[case("18446744073709551615s 1s", err(OutOfRange, 22))]
+    fn test_parse_units(#[case] s: &str, #[case] r: Result<Duration, ParseDurationError>) {
+        assert_eq!(parse_units(s), r);
+    }
+
+    #[rstest]
+    #[case("PT0S", Ok(Duration::ZERO))]
+    #[case("PT1H2M3.4S", Ok(Duration::from_millis(3_723_400)))]
+    #[case("PT3,4S", Ok(Duration::from_millis(3_400)))]
+    #[case("P1D", Ok(Duration::from_secs(86_400)))]
+    #[case("P2W", Ok(Duration::from_secs(1_209_600)))]
+    #[case("P1DT12H", Ok(Duration::from_secs(129_600)))]
+    #[case("PT0.5H", Ok(Duration::from_secs(1800)))]
+    #[case("PT36H", Ok(Duration::from_secs(129_600)))]
+    #[case("P", err(UnexpectedEnd, 1))]
+    #[case("PT", err(UnexpectedEnd, 2))]
+    #[case("P1DT", err(UnexpectedEnd, 4))]
+    #[case("1H", err(UnexpectedChar('1'), 0))]
+    #[case("pt1h", err(UnexpectedChar('p'), 0))]
+    #[case("P1Y", err(NominalUnit, 2))]
+    #[case("P1M", err(NominalUnit, 2))]
+    #[case("P1H", err(UnexpectedChar('H'), 2))]
+    #[case("PT1M1H", err(UnexpectedChar('H'), 5))]
+    #[case("PT1S1S", err(UnexpectedChar('S'), 5))]
+    #[case("PT1", err(MissingUnit, 3))]
+    #[case("PT1SX", err(UnexpectedChar('X'), 4))]
+    fn test_parse_iso8601(#[case] s: &str, #[case] r: Result<Duration, ParseDurationError>) {
+        assert_eq!(parse_iso8601(s), r);
+    }
+
+    #[rstest]
+    #[case("00:00:00", Ok(Duration::ZERO))]
+    #[case("01:02:03.400", Ok(Duration::from_millis(3_723_400)))]
+    #[case("1:02:03", Ok(Duration::from_secs(3723)))]
+    #[case("100:00:00", Ok(Duration::from_secs(360_000)))]
+    #[case("2:30", Ok(Duration::from_secs(150)))]
+    #[case("90:30.5", Ok(Duration::from_millis(5_430_500)))]
+    #[case("01:60:00", err(FieldOutOfRange, 3))]
+    #[case("01:00:60", err(FieldOutOfRange, 6))]
+    #[case("01:2:03", err(UnexpectedChar(':'), 4))]
+    #[case("01:002:03", err(UnexpectedChar('2'), 5))]
+    #[case("01:02:03.", err(UnexpectedEnd, 9))]
+    #[case("01:02.5:03", err(UnexpectedChar(':'), 7))]
+    #[case("01.5:02:03", err(UnexpectedChar('.'), 2))]
+    #[case("01", err(UnexpectedEnd, 2))]
+    #[case(":01", err(UnexpectedChar(':'), 0))]
+    #[case("01:02:03:04", err(UnexpectedChar(':'), 8))]
+    fn test_parse_clock(#[case] s: &str, #[case] r: Result<Duration, ParseDurationError>) {
+        assert_eq!(parse_clock(s), r);
+    }
+
+    #[test]
+    fn test_error_display() {
+        let e = parse_units("5x").unwrap_err();
+        assert_eq!(e.to_string(), "unknown unit at position 1");
+        let e = parse_seconds("1-").unwrap_err();
+        assert_eq!(e.to_string(), "unexpected character '-' at position 1");
+    }
+
+    fn arb_duration() -> impl Strategy<Value = Duration> {
+        prop_oneof![
+            (any::<u64>(), 0u32..1_000_000_000).prop_map(|(s, n)| Duration::new(s, n)),
+            (0u64..1_000_000, 0u32..1_000_000_000).prop_map(|(s, n)| Duration::new(s, n)),
+            (0u64..1_000).prop_map(Duration::from_millis),
+            (0u64..1_000_000).prop_map(Duration::from_nanos),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip_seconds(d in arb_duration()) {
+            let s = d.display_as_seconds().to_string();
+            prop_assert_eq!(parse_seconds(&s), Ok(d));
+            prop_assert_eq!(parse_duration(&s), Ok(d));
+        }
+
+        #[test]
+        fn roundtrip_humanized(d in arb_duration()) {
+            let s = d.display_humanized().to_string();
+            prop_assert_eq!(parse_units(&s), Ok(d));
+            prop_assert_eq!(parse_duration(&s), Ok(d));
+        }
+
+        #[test]
+        fn roundtrip_go(d in arb_duration()) {
+            let s = d.display_go().to_string();
+            prop_assert_eq!(parse_units(&s), Ok(d));
+            prop_assert_eq!(parse_duration(&s), Ok(d));
+        }
+
+        #[test]
+        fn roundtrip_iso8601(d in arb_duration()) {
+            let s = d.display_iso8601().to_string();
+            prop_assert_eq!(parse_iso8601(&s), Ok(d));
+            prop_assert_eq!(parse_duration(&s), Ok(d));
+        }
+
+        #[test]
+        fn roundtrip_clock(d in arb_duration()) {
+            let s = d.display_clock().precision(9).to_string();
+            prop_assert_eq!(parse_clock(&s), Ok(d));
+            prop_assert_eq!(parse_duration(&s), Ok(d));
+        }
+
+        #[test]
+        fn roundtrip_rounded(secs in 0u64..1_000_000_000, nanos in 0u32..1_000_000_000, precision in 0usize..12) {
+            // Whatever a formatter rounds to must parse back to exactly the
+            // displayed value.
+            let d = Duration::new(secs, nanos);
+            let s = format!("{:.*}", precision, d.display_humanized());
+            let p = parse_units(&s).unwrap();
+            prop_assert_eq!(format!("{:.*}", precision, p.display_humanized()), s);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::iter::FusedIterator;
+
+/// Returns an iterator over all sequences of `k` distinct elements of `0..n`,
+/// in lexicographic order.  Passing `k == n` iterates over all permutations
+/// of `0..n`.
+///
+/// If `k` is zero, the iterator yields a single empty `Vec`; if `k` is
+/// greater than `n`, the iterator is empty.
+///
+/// Each permutation's position in the output can be computed with
+/// [`PermutationsUpto::rank()`], and [`Iterator::nth()`] skips ahead in
+/// `O(n * k)` time, so an interrupted traversal can be resumed from a saved
+/// index.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::permutations_upto::permutations_upto;
+/// let perms = permutations_upto(3, 3).collect::<Vec<_>>();
+/// assert_eq!(
+///     perms,
+///     [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]]
+/// );
+///
+/// let mut iter = permutations_upto(4, 2);
+/// assert_eq!(iter.len(), 12);
+/// assert_eq!(iter.rank(&[2, 1]), Some(7));
+/// assert_eq!(iter.nth(7), Some(vec![2, 1]));
+/// assert_eq!(iter.next(), Some(vec![2, 3]));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the number of permutations exceeds `usize::MAX`
+pub fn permutations_upto(n: usize, k: usize) -> PermutationsUpto {
+    let len = falling_factorial(n, k).expect("number of permutations should not exceed usize::MAX");
+    PermutationsUpto {
+        k,
+        len,
+        pos: 0,
+        next: (0..n).collect(),
+    }
+}
+
+/// Iterator returned by [`permutations_upto()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PermutationsUpto {
+    k: usize,
+    len: usize,
+    pos: usize,
+    // The first `k` elements are the next permutation to yield; the rest are
+    // the unused elements in ascending order.
+    next: Vec<usize>,
+}
+
+impl PermutationsUpto {
+    fn n(&self) -> usize {
+        self.next.len()
+    }
+
+    /// Returns the number of permutations that have been yielded so far,
+    /// which is also the rank of the next permutation to be yielded
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the index of `perm` in the complete output of this iterator,
+    /// or `None` if `perm` is not part of the output (i.e., if it is not a
+    /// sequence of `k` distinct elements of `0..n`)
+    pub fn rank(&self, perm: &[usize]) -> Option<usize> {
+        let n = self.n();
+        if perm.len() != self.k {
+            return None;
+        }
+        let mut used = vec![false; n];
+        let mut rank = 0;
+        for (t, &x) in perm.iter().enumerate() {
+            if std::mem::replace(used.get_mut(x)?, true) {
+                return None;
+            }
+            let smaller_unused = used.iter().take(x).filter(|&&u| !u).count();
+            rank += smaller_unused * self.count_after(t);
+        }
+        Some(rank)
+    }
+
+    /// Returns the permutation at index `index` in the complete output of
+    /// this iterator, or `None` if `index` is out of range
+    pub fn unrank(&self, index: usize) -> Option<Vec<usize>> {
+        let mut arrangement = self.arrangement(index)?;
+        arrangement.truncate(self.k);
+        Some(arrangement)
+    }
+
+    /// Returns the permutation at index `index` followed by the unused
+    /// elements in ascending order
+    fn arrangement(&self, mut index: usize) -> Option<Vec<usize>> {
+        if index >= self.len {
+            return None;
+        }
+        let mut unused = (0..self.n()).collect::<Vec<_>>();
+        let mut arrangement = Vec::with_capacity(self.n());
+        for t in 0..self.k {
+            let block = self.count_after(t);
+            arrangement.push(unused.remove(index / block));
+            index %= block;
+        }
+        arrangement.extend(unused);
+        Some(arrangement)
+    }
+
+    /// Returns the number of permutations that share a given prefix of
+    /// length `t + 1`
+    fn count_after(&self, t: usize) -> usize {
+        // This is always at most `self.len`, so it cannot overflow.
+        falling_factorial(self.n() - t - 1, self.k - t - 1).unwrap_or(usize::MAX)
+    }
+
+    /// Advance `self.next` to the lexicographically next arrangement
+    fn advance(&mut self) {
+        // Reversing the unused elements makes them the lexicographically
+        // greatest arrangement of the tail, so the next full permutation of
+        // `self.next` begins with the next `k`-permutation.
+        self.next[self.k..].reverse();
+        let advanced = next_permutation(&mut self.next);
+        debug_assert!(
+            advanced,
+            "advance() should not be called on the last permutation"
+        );
+    }
+}
+
+impl Iterator for PermutationsUpto {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        if self.pos >= self.len {
+            return None;
+        }
+        let perm = self.next[..self.k].to_vec();
+        self.pos += 1;
+        if self.pos < self.len {
+            self.advance();
+        }
+        Some(perm)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Vec<usize>> {
+        if let Some(arrangement) = self.pos.checked_add(n).and_then(|i| self.arrangement(i)) {
+            self.pos += n;
+            self.next = arrangement;
+            self.next()
+        } else {
+            self.pos = self.len;
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sz = self.len - self.pos;
+        (sz, Some(sz))
+    }
+}
+
+impl FusedIterator for PermutationsUpto {}
+
+impl ExactSizeIterator for PermutationsUpto {}
+
+/// Rearrange `arr` into the lexicographically next permutation of its
+/// elements.  Returns `false` if `arr` is already the greatest permutation.
+fn next_permutation(arr: &mut [usize]) -> bool {
+    let Some(i) = arr.windows(2).rposition(|w| matches!(w, [a, b] if a < b)) else {
+        return false;
+    };
+    let pivot = arr[i];
+    if let Some(j) = arr.iter().rposition(|&x| x > pivot) {
+        arr.swap(i, j);
+    }
+    arr[i + 1..].reverse();
+    true
+}
+
+/// Returns `n * (n - 1) * ... * (n - k + 1)`, or `None` on overflow
+fn falling_factorial(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    (n - k + 1..=n).try_fold(1usize, usize::checked_mul)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cross_upto_n::cross_upto_n;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn brute_force(n: usize, k: usize) -> Vec<Vec<usize>> {
+        cross_upto_n(&vec![n; k])
+            .filter(|v| v.iter().collect::<BTreeSet<_>>().len() == v.len())
+            .collect()
+    }
+
+    #[test]
+    fn test_small() {
+        for n in 0..6 {
+            for k in 0..=n + 1 {
+                let expected = brute_force(n, k);
+                let mut iter = permutations_upto(n, k);
+                assert_eq!(iter.len(), expected.len(), "n={n}, k={k}");
+                for (r, perm) in expected.iter().enumerate() {
+                    assert_eq!(iter.position(), r);
+                    assert_eq!(iter.rank(perm), Some(r));
+                    assert_eq!(iter.unrank(r).as_ref(), Some(perm));
+                    assert_eq!(iter.next().as_ref(), Some(perm));
+                }
+                assert_eq!(iter.next(), None);
+                assert_eq!(iter.unrank(expected.len()), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_invalid() {
+        let iter = permutations_upto(5, 3);
+        assert_eq!(iter.rank(&[0, 1]), None);
+        assert_eq!(iter.rank(&[0, 2, 2]), None);
+        assert_eq!(iter.rank(&[1, 2, 5]), None);
+    }
+
+    #[test]
+    fn test_falling_factorial() {
+        assert_eq!(falling_factorial(0, 0), Some(1));
+        assert_eq!(falling_factorial(5, 0), Some(1));
+        assert_eq!(falling_factorial(5, 2), Some(20));
+        assert_eq!(falling_factorial(5, 5), Some(120));
+        assert_eq!(falling_factorial(2, 5), Some(0));
+        assert_eq!(falling_factorial(20, 20), Some(2_432_902_008_176_640_000));
+        assert_eq!(falling_factorial(21, 21), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "number of permutations should not exceed usize::MAX")]
+    fn test_overflow() {
+        let _ = permutations_upto(21, 21);
+    }
+
+    #[test]
+    fn test_large() {
+        let mut iter = permutations_upto(20, 20);
+        let last = iter.len() - 1;
+        let top = (0..20).rev().collect::<Vec<_>>();
+        assert_eq!(iter.unrank(last).as_ref(), Some(&top));
+        assert_eq!(iter.rank(&top), Some(last));
+        let penultimate = (2..20).rev().chain([0, 1]).collect::<Vec<_>>();
+        assert_eq!(iter.nth(last - 1), Some(penultimate));
+        assert_eq!(iter.next(), Some(top));
+        assert_eq!(iter.next(), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_nth(n in 0usize..7, k in 0usize..5, skip1 in 0usize..200, skip2 in 0usize..200) {
+            let expected = permutations_upto(n, k).collect::<Vec<_>>();
+            let mut iter = permutations_upto(n, k);
+            prop_assert_eq!(iter.nth(skip1), expected.get(skip1).cloned());
+            prop_assert_eq!(iter.next(), expected.get(skip1 + 1).cloned());
+            prop_assert_eq!(iter.nth(skip2), expected.get(skip1 + skip2 + 2).cloned());
+            prop_assert_eq!(iter.len(), expected.len().saturating_sub(skip1 + skip2 + 3));
+        }
+    }
+}
@@ -1,91 +1,915 @@
-use std::ops::{Bound, RangeBounds};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::num::NonZeroUsize;
+use std::ops::{Bound, RangeBounds, RangeInclusive};
 
 /// Given a range in which there exists an `x` such that `!predicate(i)` for
 /// all `i < x` in the range and `predicate(i)` for all `i >= x` in the range,
 /// find & return `x` via a binary search.  Returns `None` if `x` could not be
 /// found (meaning that some precondition was violated).
-pub fn first_in_range<R, P>(range: R, mut predicate: P) -> Option<usize>
-where
-    R: RangeBounds<usize>,
-    P: FnMut(usize) -> bool,
-{
-    let lower_bound = (range.start_bound().cloned(), Bound::Unbounded);
-    let mut bounds = BinsearchBounds::from(range);
-    while let Some(mid) = bounds.midpoint() {
-        if predicate(mid) {
-            if mid
-                .checked_sub(1)
-                .is_some_and(|prev| lower_bound.contains(&prev) && predicate(prev))
-            {
-                bounds = bounds.below(mid);
-            } else {
-                return Some(mid);
-            }
-        } else {
-            bounds = bounds.above(mid);
-        }
+///
+/// The range may be over any primitive integer type, including signed types
+/// and ranges that span zero.
+pub fn first_in_range<T, R, P>(range: R, mut predicate: P) -> Option<T>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> bool,
+{
+    match try_first_in_range(range, |x| Ok::<_, Infallible>(predicate(x))) {
+        Ok(r) => r,
+        Err(e) => match e {},
     }
-    None
 }
 
 /// Given a range in which there exists an `x` such that `predicate(i)` for all
 /// `i <= x` in the range and `!predicate(i)` for all `i > x` in the range,
 /// find & return `x` via a binary search.  Returns `None` if `x` could not be
 /// found (meaning that some precondition was violated).
-pub fn last_in_range<R, P>(range: R, mut predicate: P) -> Option<usize>
-where
-    R: RangeBounds<usize>,
-    P: FnMut(usize) -> bool,
-{
-    let upper_bound = (Bound::Unbounded, range.end_bound().cloned());
-    let mut bounds = BinsearchBounds::from(range);
-    while let Some(mid) = bounds.midpoint() {
-        if predicate(mid) {
-            if mid
-                .checked_add(1)
-                .is_some_and(|next| upper_bound.contains(&next) && predicate(next))
-            {
-                bounds = bounds.above(mid + 1);
-            } else {
-                return Some(mid);
+///
+/// The range may be over any primitive integer type, including signed types
+/// and ranges that span zero.
+pub fn last_in_range<T, R, P>(range: R, mut predicate: P) -> Option<T>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> bool,
+{
+    match try_last_in_range(range, |x| Ok::<_, Infallible>(predicate(x))) {
+        Ok(r) => r,
+        Err(e) => match e {},
+    }
+}
+
+/// Like [`first_in_range()`], but the predicate is fallible.  The first error
+/// returned by the predicate ends the search and is returned.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::try_first_in_range;
+/// let r = try_first_in_range(0..100, |x| Ok::<_, String>(x >= 42));
+/// assert_eq!(r, Ok(Some(42)));
+///
+/// let r = try_first_in_range(0..100, |x| {
+///     if x == 49 { Err(format!("build {x} failed")) } else { Ok(x >= 42) }
+/// });
+/// assert_eq!(r, Err(String::from("build 49 failed")));
+/// ```
+pub fn try_first_in_range<T, R, P, E>(range: R, mut predicate: P) -> Result<Option<T>, E>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> Result<bool, E>,
+{
+    let mut search = Bisection::first(range);
+    loop {
+        match search.step() {
+            Step::Test(x) => search.record(predicate(x)?),
+            Step::Done(r) => return Ok(r),
+        }
+    }
+}
+
+/// Like [`last_in_range()`], but the predicate is fallible.  The first error
+/// returned by the predicate ends the search and is returned.
+pub fn try_last_in_range<T, R, P, E>(range: R, mut predicate: P) -> Result<Option<T>, E>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> Result<bool, E>,
+{
+    let mut search = Bisection::last(range);
+    loop {
+        match search.step() {
+            Step::Test(x) => search.record(predicate(x)?),
+            Step::Done(r) => return Ok(r),
+        }
+    }
+}
+
+/// Like [`first_in_range()`], but the predicate is asynchronous.  Predicate
+/// calls are awaited one at a time.
+pub async fn first_in_range_async<T, R, P, Fut>(range: R, mut predicate: P) -> Option<T>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut search = Bisection::first(range);
+    loop {
+        match search.step() {
+            Step::Test(x) => search.record(predicate(x).await),
+            Step::Done(r) => return r,
+        }
+    }
+}
+
+/// Like [`last_in_range()`], but the predicate is asynchronous.  Predicate
+/// calls are awaited one at a time.
+pub async fn last_in_range_async<T, R, P, Fut>(range: R, mut predicate: P) -> Option<T>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut search = Bisection::last(range);
+    loop {
+        match search.step() {
+            Step::Test(x) => search.record(predicate(x).await),
+            Step::Done(r) => return r,
+        }
+    }
+}
+
+/// Like [`first_in_range()`], but the predicate may return `None` to indicate
+/// that an index cannot be tested, in the manner of `git bisect skip`.  When
+/// the midpoint of the remaining range is skipped, the nearest untested
+/// indices on either side are tried instead.  The predicate is called at most
+/// once for each index.
+///
+/// Returns the range of indices in which `x` is known to lie: this is `x..=x`
+/// if `x` was located exactly, or else `a..=x` where every index in `a..x`
+/// was skipped, so that any of them could be the true answer.  Returns `None`
+/// if `predicate` did not return `Some(true)` for any index.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::first_in_range_skipping;
+/// let r = first_in_range_skipping(0..100, |x| (x != 50).then_some(x >= 42));
+/// assert_eq!(r, Some(42..=42));
+///
+/// let r = first_in_range_skipping(0..100, |x| (!(40..45).contains(&x)).then_some(x >= 42));
+/// assert_eq!(r, Some(40..=45));
+/// ```
+pub fn first_in_range_skipping<T, R, P>(range: R, mut predicate: P) -> Option<RangeInclusive<T>>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> Option<bool>,
+{
+    let (low, high) = BinsearchBounds::new(range).endpoints()?;
+    let mut last_false = None;
+    let mut first_true = None;
+    let mut skipped = BTreeSet::new();
+    loop {
+        let a = last_false.map_or(Some(low), T::checked_succ);
+        let b = first_true.map_or(Some(high), T::checked_pred);
+        let Some(probe) = a.zip(b).and_then(|(a, b)| probe_untested(a, b, &skipped)) else {
+            break;
+        };
+        match predicate(probe) {
+            Some(true) => first_true = Some(probe),
+            Some(false) => last_false = Some(probe),
+            None => {
+                skipped.insert(probe);
             }
-        } else {
-            bounds = bounds.below(mid);
         }
     }
+    let first_true = first_true?;
+    let start = last_false.and_then(T::checked_succ).unwrap_or(low);
+    Some(start..=first_true)
+}
+
+/// Like [`last_in_range()`], but the predicate may return `None` to indicate
+/// that an index cannot be tested, in the manner of `git bisect skip`.  When
+/// the midpoint of the remaining range is skipped, the nearest untested
+/// indices on either side are tried instead.  The predicate is called at most
+/// once for each index.
+///
+/// Returns the range of indices in which `x` is known to lie: this is `x..=x`
+/// if `x` was located exactly, or else `x..=b` where every index in
+/// `x+1..=b` was skipped, so that any of them could be the true answer.
+/// Returns `None` if `predicate` did not return `Some(true)` for any index.
+pub fn last_in_range_skipping<T, R, P>(range: R, mut predicate: P) -> Option<RangeInclusive<T>>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> Option<bool>,
+{
+    let (low, high) = BinsearchBounds::new(range).endpoints()?;
+    let mut last_true = None;
+    let mut first_false = None;
+    let mut skipped = BTreeSet::new();
+    loop {
+        let a = last_true.map_or(Some(low), T::checked_succ);
+        let b = first_false.map_or(Some(high), T::checked_pred);
+        let Some(probe) = a.zip(b).and_then(|(a, b)| probe_untested(a, b, &skipped)) else {
+            break;
+        };
+        match predicate(probe) {
+            Some(true) => last_true = Some(probe),
+            Some(false) => first_false = Some(probe),
+            None => {
+                skipped.insert(probe);
+            }
+        }
+    }
+    let last_true = last_true?;
+    let end = first_false.and_then(T::checked_pred).unwrap_or(high);
+    Some(last_true..=end)
+}
+
+/// Returns the index in `a..=b` that is nearest to the midpoint of the range
+/// and not in `skipped`, or `None` if there is no such index
+fn probe_untested<T: BisectInt>(a: T, b: T, skipped: &BTreeSet<T>) -> Option<T> {
+    if a > b {
+        return None;
+    }
+    let mid = a.floor_midpoint(b);
+    let mut up = Some(mid);
+    let mut down = mid.checked_pred().filter(|&d| d >= a);
+    while up.is_some() || down.is_some() {
+        if let Some(u) = up {
+            if !skipped.contains(&u) {
+                return Some(u);
+            }
+            up = u.checked_succ().filter(|&u| u <= b);
+        }
+        if let Some(d) = down {
+            if !skipped.contains(&d) {
+                return Some(d);
+            }
+            down = d.checked_pred().filter(|&d| d >= a);
+        }
+    }
+    None
+}
+
+/// Wrap `predicate` so that it is called at most once for each distinct
+/// argument, with later calls for the same argument returning the cached
+/// result.  This is useful when passing an expensive predicate to
+/// [`first_in_range()`] or [`last_in_range()`], which may evaluate the same
+/// index more than once.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::{first_in_range, memoize};
+/// let mut calls = Vec::new();
+/// let r = first_in_range(0..100, memoize(|x| {
+///     calls.push(x);
+///     x >= 42
+/// }));
+/// assert_eq!(r, Some(42));
+/// let mut sorted = calls.clone();
+/// sorted.sort_unstable();
+/// sorted.dedup();
+/// assert_eq!(sorted.len(), calls.len());
+/// ```
+pub fn memoize<T, P>(mut predicate: P) -> impl FnMut(T) -> bool
+where
+    T: Copy + Ord,
+    P: FnMut(T) -> bool,
+{
+    let mut cache = BTreeMap::new();
+    move |x| *cache.entry(x).or_insert_with(|| predicate(x))
+}
+
+/// Like [`first_in_range()`], but performs a `k`-ary search in which each
+/// round evaluates the predicate at up to `probes` evenly-spaced indices in
+/// parallel, each on its own thread, shrinking the range by a factor of about
+/// `probes + 1` per round.  The predicate is called at most once for each
+/// index.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::par_first_in_range;
+/// # use std::num::NonZeroUsize;
+/// let probes = NonZeroUsize::new(4).unwrap();
+/// assert_eq!(par_first_in_range(0..1000, probes, |x| x >= 617), Some(617));
+/// ```
+pub fn par_first_in_range<T, R, P>(range: R, probes: NonZeroUsize, predicate: P) -> Option<T>
+where
+    T: BisectInt + Send,
+    R: RangeBounds<T>,
+    P: Fn(T) -> bool + Sync,
+{
+    let (low, high) = BinsearchBounds::new(range).endpoints()?;
+    // The answer, if any, is either in `untested` or is `first_true`.
+    let mut untested = Some((low, high));
+    let mut first_true = None;
+    while let Some((a, b)) = untested {
+        let points = probe_points(a, b, probes.get());
+        let predicate = &predicate;
+        let results = std::thread::scope(|scope| {
+            let handles = points
+                .iter()
+                .map(|&x| scope.spawn(move || predicate(x)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect::<Vec<_>>()
+        });
+        let split = results.iter().position(|&r| r).unwrap_or(points.len());
+        let start = match split.checked_sub(1) {
+            Some(i) => points[i].checked_succ(),
+            None => Some(a),
+        };
+        let end = match points.get(split) {
+            Some(&x) => {
+                first_true = Some(x);
+                x.checked_pred()
+            }
+            None => Some(b),
+        };
+        untested = start.zip(end).filter(|(s, e)| s <= e);
+    }
+    first_true
+}
+
+/// Like [`last_in_range()`], but performs a `k`-ary search in which each
+/// round evaluates the predicate at up to `probes` evenly-spaced indices in
+/// parallel, each on its own thread, shrinking the range by a factor of about
+/// `probes + 1` per round.  The predicate is called at most once for each
+/// index.
+pub fn par_last_in_range<T, R, P>(range: R, probes: NonZeroUsize, predicate: P) -> Option<T>
+where
+    T: BisectInt + Send,
+    R: RangeBounds<T>,
+    P: Fn(T) -> bool + Sync,
+{
+    let (low, high) = BinsearchBounds::new(range).endpoints()?;
+    match par_first_in_range(low..=high, probes, |x| !predicate(x)) {
+        Some(first_false) => first_false.checked_pred().filter(|&x| x >= low),
+        None => Some(high),
+    }
+}
+
+/// Returns up to `k` distinct indices in `a..=b`, in ascending order, spaced
+/// as evenly as possible by repeatedly bisecting the range
+fn probe_points<T: BisectInt>(a: T, b: T, k: usize) -> Vec<T> {
+    let mut points = Vec::with_capacity(k);
+    let mut queue = VecDeque::from([(a, b)]);
+    while points.len() < k
+        && let Some((a, b)) = queue.pop_front()
+    {
+        let mid = a.floor_midpoint(b);
+        points.push(mid);
+        if let Some(m) = mid.checked_pred()
+            && a <= m
+        {
+            queue.push_back((a, m));
+        }
+        if let Some(m) = mid.checked_succ()
+            && m <= b
+        {
+            queue.push_back((m, b));
+        }
+    }
+    points.sort_unstable();
+    points
+}
+
+/// Strategy used by [`first_in_range_with()`] and [`last_in_range_with()`] to
+/// locate the point at which a predicate changes
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SearchStrategy {
+    /// Bisect the whole range, as done by [`first_in_range()`] and
+    /// [`last_in_range()`]
+    #[default]
+    Bisect,
+
+    /// Probe the range's lower bound plus 0, 1, 2, 4, 8, … until the
+    /// predicate changes, then bisect between the last two probes.  This
+    /// takes `O(log d)` evaluations of the predicate, where `d` is the
+    /// distance from the lower bound to the answer, making it far cheaper
+    /// than [`SearchStrategy::Bisect`] when the range is large or unbounded
+    /// but the answer is close to the lower bound.
+    Gallop,
+}
+
+/// The result of [`first_in_range_with()`] or [`last_in_range_with()`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SearchOutcome<T> {
+    /// The value found, or `None` if the search's precondition was violated
+    pub found: Option<T>,
+
+    /// The number of times the predicate was called
+    pub evaluations: usize,
+}
+
+/// Like [`first_in_range()`], but using the given search strategy and also
+/// reporting the number of times the predicate was evaluated
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::{first_in_range_with, SearchStrategy};
+/// let outcome = first_in_range_with(0u64.., SearchStrategy::Gallop, |x| x * x >= 1000);
+/// assert_eq!(outcome.found, Some(32));
+/// assert!(outcome.evaluations <= 12);
+/// ```
+pub fn first_in_range_with<T, R, P>(
+    range: R,
+    strategy: SearchStrategy,
+    mut predicate: P,
+) -> SearchOutcome<T>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> bool,
+{
+    let mut evaluations = 0;
+    let counted = |x| {
+        evaluations += 1;
+        predicate(x)
+    };
+    let found = match strategy {
+        SearchStrategy::Bisect => first_in_range(range, counted),
+        SearchStrategy::Gallop => gallop_first(BinsearchBounds::new(range), counted),
+    };
+    SearchOutcome { found, evaluations }
+}
+
+/// Like [`last_in_range()`], but using the given search strategy and also
+/// reporting the number of times the predicate was evaluated
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::{last_in_range_with, SearchStrategy};
+/// let outcome = last_in_range_with(0usize.., SearchStrategy::Gallop, |x| x < 5);
+/// assert_eq!(outcome.found, Some(4));
+/// assert!(outcome.evaluations <= 7);
+/// ```
+pub fn last_in_range_with<T, R, P>(
+    range: R,
+    strategy: SearchStrategy,
+    mut predicate: P,
+) -> SearchOutcome<T>
+where
+    T: BisectInt,
+    R: RangeBounds<T>,
+    P: FnMut(T) -> bool,
+{
+    let mut evaluations = 0;
+    let counted = |x| {
+        evaluations += 1;
+        predicate(x)
+    };
+    let found = match strategy {
+        SearchStrategy::Bisect => last_in_range(range, counted),
+        SearchStrategy::Gallop => gallop_last(BinsearchBounds::new(range), counted),
+    };
+    SearchOutcome { found, evaluations }
+}
+
+fn gallop_first<T: BisectInt, P: FnMut(T) -> bool>(
+    bounds: BinsearchBounds<T>,
+    mut predicate: P,
+) -> Option<T> {
+    let (low, high) = bounds.endpoints()?;
+    if predicate(low) {
+        return Some(low);
+    }
+    let (no, yes) = gallop(low, high, |x| !predicate(x))?;
+    Some(bisect_between(no, yes, |x| !predicate(x)).1)
+}
+
+fn gallop_last<T: BisectInt, P: FnMut(T) -> bool>(
+    bounds: BinsearchBounds<T>,
+    mut predicate: P,
+) -> Option<T> {
+    let (low, high) = bounds.endpoints()?;
+    if !predicate(low) {
+        return None;
+    }
+    match gallop(low, high, &mut predicate) {
+        Some((yes, no)) => Some(bisect_between(yes, no, predicate).0),
+        None => Some(high),
+    }
+}
+
+/// Given that `in_lower(low)` is true, probe `low + 1`, `low + 2`, `low + 4`,
+/// … (capped at `high`) until `in_lower` returns false, and return the last
+/// probe for which it was true along with the probe for which it was false.
+/// Returns `None` if `in_lower` was true for all probes up through `high`.
+fn gallop<T: BisectInt, P: FnMut(T) -> bool>(low: T, high: T, mut in_lower: P) -> Option<(T, T)> {
+    let mut last = low;
+    // `None` once the step has outgrown `T`, at which point the next probe
+    // is `high`
+    let mut step = Some(T::ONE);
+    while last < high {
+        let probe = step
+            .and_then(|s| low.checked_plus(s))
+            .filter(|&p| p <= high)
+            .unwrap_or(high);
+        if !in_lower(probe) {
+            return Some((last, probe));
+        }
+        last = probe;
+        step = step.and_then(|s| s.checked_plus(s));
+    }
     None
 }
 
-struct BinsearchBounds {
-    start: Bound<usize>,
-    end: Bound<usize>,
+/// Given `lo < hi` where `in_lower(lo)` is true and `in_lower(hi)` is false,
+/// narrow the interval via bisection until `lo + 1 == hi`, and return the
+/// final `(lo, hi)`
+fn bisect_between<T: BisectInt, P: FnMut(T) -> bool>(
+    mut lo: T,
+    mut hi: T,
+    mut in_lower: P,
+) -> (T, T) {
+    while lo.checked_succ() != Some(hi) {
+        let mid = lo.floor_midpoint(hi);
+        if in_lower(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo, hi)
+}
+
+/// Primitive integer types that can be searched over by [`first_in_range()`]
+/// and [`last_in_range()`]
+pub trait BisectInt: Copy + Debug + Ord {
+    const MIN: Self;
+    const MAX: Self;
+    const ONE: Self;
+
+    /// Returns `self + other`, or `None` on overflow
+    fn checked_plus(self, other: Self) -> Option<Self>;
+
+    /// Returns `self + 1`, or `None` on overflow
+    fn checked_succ(self) -> Option<Self>;
+
+    /// Returns `self - 1`, or `None` on overflow
+    fn checked_pred(self) -> Option<Self>;
+
+    /// Returns the average of `self` and `other`, rounded toward negative
+    /// infinity, without overflowing
+    fn floor_midpoint(self, other: Self) -> Self;
+}
+
+macro_rules! impl_bisect_int {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl BisectInt for $t {
+                const MIN: $t = <$t>::MIN;
+                const MAX: $t = <$t>::MAX;
+                const ONE: $t = 1;
+
+                fn checked_plus(self, other: $t) -> Option<$t> {
+                    self.checked_add(other)
+                }
+
+                fn checked_succ(self) -> Option<$t> {
+                    self.checked_add(1)
+                }
+
+                fn checked_pred(self) -> Option<$t> {
+                    self.checked_sub(1)
+                }
+
+                fn floor_midpoint(self, other: $t) -> $t {
+                    // The shift is arithmetic for signed types, so this
+                    // rounds toward negative infinity for all types.
+                    (self & other) + ((self ^ other) >> 1)
+                }
+            }
+        )*
+    };
+}
+
+impl_bisect_int!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+/// How precisely [`first_in_range_f64()`] and [`last_in_range_f64()`] should
+/// locate the point at which the predicate changes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatPrecision {
+    /// Stop bisecting once the point is known to lie within an interval of
+    /// at most the given width.  The range's bounds must be finite.
+    Tolerance(f64),
+
+    /// Bisect over the ordered bit patterns of the floats in the range until
+    /// the point is located exactly, which takes at most 64 bisection steps.
+    /// Infinite bounds are allowed.
+    Exact,
+}
+
+/// Given a range `low..=high` of `f64`s in which `!predicate(x)` for all `x`
+/// below some threshold and `predicate(x)` for all `x` at or above it, find
+/// the threshold via a binary search.
+///
+/// With [`FloatPrecision::Exact`], the return value is the smallest `f64` in
+/// the range for which `predicate` is true.  With
+/// [`FloatPrecision::Tolerance`], the return value is a value for which
+/// `predicate` is true that is within the tolerance of the threshold.
+///
+/// Returns `None` if `predicate(high)` is false, if either bound is NaN, or if
+/// `low > high`.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::predicate_bsearch::{first_in_range_f64, FloatPrecision};
+/// let x = first_in_range_f64(0.0..=10.0, FloatPrecision::Tolerance(1e-9), |x| x * x >= 2.0);
+/// assert!((x.unwrap() - std::f64::consts::SQRT_2).abs() < 1e-9);
+///
+/// let x = first_in_range_f64(0.0..=10.0, FloatPrecision::Exact, |x| x >= 0.1);
+/// assert_eq!(x, Some(0.1));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `precision` is [`FloatPrecision::Tolerance`] and either bound is
+/// infinite
+pub fn first_in_range_f64<P>(
+    range: RangeInclusive<f64>,
+    precision: FloatPrecision,
+    mut predicate: P,
+) -> Option<f64>
+where
+    P: FnMut(f64) -> bool,
+{
+    let (low, high) = range.into_inner();
+    if low.is_nan() || high.is_nan() || low > high {
+        return None;
+    }
+    match precision {
+        FloatPrecision::Tolerance(tolerance) => {
+            assert!(
+                low.is_finite() && high.is_finite(),
+                "first_in_range_f64: bounds must be finite when bisecting to a tolerance"
+            );
+            if predicate(low) {
+                return Some(low);
+            }
+            if !predicate(high) {
+                return None;
+            }
+            let (_, hi) = bisect_to_tolerance(low, high, tolerance, |x| !predicate(x));
+            Some(hi)
+        }
+        FloatPrecision::Exact => {
+            let key = first_in_range(float_key(low)..=float_key(high), |k| {
+                predicate(float_from_key(k))
+            })?;
+            Some(float_from_key(key))
+        }
+    }
+}
+
+/// Given a range `low..=high` of `f64`s in which `predicate(x)` for all `x`
+/// at or below some threshold and `!predicate(x)` for all `x` above it, find
+/// the threshold via a binary search.
+///
+/// With [`FloatPrecision::Exact`], the return value is the largest `f64` in
+/// the range for which `predicate` is true.  With
+/// [`FloatPrecision::Tolerance`], the return value is a value for which
+/// `predicate` is true that is within the tolerance of the threshold.
+///
+/// Returns `None` if `predicate(low)` is false, if either bound is NaN, or if
+/// `low > high`.
+///
+/// # Panics
+///
+/// Panics if `precision` is [`FloatPrecision::Tolerance`] and either bound is
+/// infinite
+pub fn last_in_range_f64<P>(
+    range: RangeInclusive<f64>,
+    precision: FloatPrecision,
+    mut predicate: P,
+) -> Option<f64>
+where
+    P: FnMut(f64) -> bool,
+{
+    let (low, high) = range.into_inner();
+    if low.is_nan() || high.is_nan() || low > high {
+        return None;
+    }
+    match precision {
+        FloatPrecision::Tolerance(tolerance) => {
+            assert!(
+                low.is_finite() && high.is_finite(),
+                "last_in_range_f64: bounds must be finite when bisecting to a tolerance"
+            );
+            if predicate(high) {
+                return Some(high);
+            }
+            if !predicate(low) {
+                return None;
+            }
+            let (lo, _) = bisect_to_tolerance(low, high, tolerance, predicate);
+            Some(lo)
+        }
+        FloatPrecision::Exact => {
+            let key = last_in_range(float_key(low)..=float_key(high), |k| {
+                predicate(float_from_key(k))
+            })?;
+            Some(float_from_key(key))
+        }
+    }
+}
+
+/// Narrow the interval `lo..=hi` until its width is at most `tolerance` or
+/// it cannot be divided any further, replacing `lo` with the midpoint when
+/// `in_lower(midpoint)` is true and replacing `hi` otherwise
+fn bisect_to_tolerance<P>(mut lo: f64, mut hi: f64, tolerance: f64, mut in_lower: P) -> (f64, f64)
+where
+    P: FnMut(f64) -> bool,
+{
+    loop {
+        let mid = lo.midpoint(hi);
+        if hi - lo <= tolerance || mid <= lo || mid >= hi {
+            return (lo, hi);
+        }
+        if in_lower(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+}
+
+/// Map an `f64` to an `i64` such that the ordering of the integers matches
+/// [`f64::total_cmp()`]
+fn float_key(x: f64) -> i64 {
+    let bits = x.to_bits().cast_signed();
+    bits ^ ((bits >> 63).cast_unsigned() >> 1).cast_signed()
+}
+
+/// Inverse of [`float_key()`]
+fn float_from_key(key: i64) -> f64 {
+    let bits = key ^ ((key >> 63).cast_unsigned() >> 1).cast_signed();
+    f64::from_bits(bits.cast_unsigned())
+}
+
+/// The state of a binary search for the first or last index in a range that
+/// satisfies a predicate.  The caller evaluates the predicate, which lets the
+/// same search drive fallible and asynchronous predicates.
+///
+/// Each midpoint that satisfies the predicate is confirmed by testing its
+/// neighbor on the far side of the boundary being sought; if the neighbor
+/// also satisfies the predicate, the search continues past the midpoint.
+#[derive(Clone, Copy, Debug)]
+struct Bisection<T> {
+    bounds: BinsearchBounds<T>,
+    /// The bound of the original range on the side where neighbors are
+    /// tested, used to check whether a midpoint's neighbor is in range
+    limit: (Bound<T>, Bound<T>),
+    target: Target,
+    state: BisectionState<T>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Target {
+    First,
+    Last,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BisectionState<T> {
+    /// The midpoint of `bounds` is to be tested next
+    Midpoint,
+    /// The given midpoint satisfied the predicate, and its neighbor is to be
+    /// tested next
+    Neighbor(T),
+    Found(T),
+}
+
+/// The next action to take in a [`Bisection`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Step<T> {
+    /// Evaluate the predicate at the given index and pass the result to
+    /// [`Bisection::record()`]
+    Test(T),
+    /// The search is complete
+    Done(Option<T>),
 }
 
-impl BinsearchBounds {
+impl<T: BisectInt> Bisection<T> {
+    fn first<R: RangeBounds<T>>(range: R) -> Bisection<T> {
+        Bisection {
+            limit: (range.start_bound().cloned(), Bound::Unbounded),
+            bounds: BinsearchBounds::new(range),
+            target: Target::First,
+            state: BisectionState::Midpoint,
+        }
+    }
+
+    fn last<R: RangeBounds<T>>(range: R) -> Bisection<T> {
+        Bisection {
+            limit: (Bound::Unbounded, range.end_bound().cloned()),
+            bounds: BinsearchBounds::new(range),
+            target: Target::Last,
+            state: BisectionState::Midpoint,
+        }
+    }
+
+    fn step(&self) -> Step<T> {
+        match self.state {
+            BisectionState::Midpoint => match self.bounds.midpoint() {
+                Some(mid) => Step::Test(mid),
+                None => Step::Done(None),
+            },
+            BisectionState::Neighbor(mid) => match self.neighbor(mid) {
+                Some(x) => Step::Test(x),
+                None => Step::Done(Some(mid)),
+            },
+            BisectionState::Found(x) => Step::Done(Some(x)),
+        }
+    }
+
+    /// Record the result of evaluating the predicate at the index returned
+    /// by the last call to [`Bisection::step()`]
+    fn record(&mut self, satisfied: bool) {
+        match self.state {
+            BisectionState::Midpoint => {
+                let Some(mid) = self.bounds.midpoint() else {
+                    return;
+                };
+                if satisfied {
+                    self.state = BisectionState::Neighbor(mid);
+                } else {
+                    self.bounds = match self.target {
+                        Target::First => self.bounds.above(mid),
+                        Target::Last => self.bounds.below(mid),
+                    };
+                }
+            }
+            BisectionState::Neighbor(mid) => {
+                if satisfied {
+                    self.bounds = match self.target {
+                        Target::First => self.bounds.below(mid),
+                        Target::Last => self.bounds.above(mid),
+                    };
+                    self.state = BisectionState::Midpoint;
+                } else {
+                    self.state = BisectionState::Found(mid);
+                }
+            }
+            BisectionState::Found(_) => (),
+        }
+    }
+
+    /// Returns the index adjacent to `mid` on the side of the boundary
+    /// being sought, if it is in range
+    fn neighbor(&self, mid: T) -> Option<T> {
+        match self.target {
+            Target::First => mid.checked_pred(),
+            Target::Last => mid.checked_succ(),
+        }
+        .filter(|x| self.limit.contains(x))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BinsearchBounds<T> {
+    start: Bound<T>,
+    end: Bound<T>,
+}
+
+impl<T: BisectInt> BinsearchBounds<T> {
+    fn new<R: RangeBounds<T>>(bounds: R) -> BinsearchBounds<T> {
+        BinsearchBounds {
+            start: bounds.start_bound().cloned(),
+            end: bounds.end_bound().cloned(),
+        }
+    }
+
+    fn midpoint(&self) -> Option<T> {
+        let (low, high) = self.endpoints()?;
+        Some(low.floor_midpoint(high))
+    }
+
+    /// Returns the least and greatest values in the bounds, or `None` if the
+    /// bounds are empty
     // We need to be lazy in order to avoid underflow
     #[allow(clippy::unnecessary_lazy_evaluations)]
-    fn midpoint(&self) -> Option<usize> {
+    fn endpoints(&self) -> Option<(T, T)> {
         let low = match self.start {
             Bound::Included(b) => b,
-            Bound::Excluded(b) => b.checked_add(1)?,
-            Bound::Unbounded => usize::MIN,
+            Bound::Excluded(b) => b.checked_succ()?,
+            Bound::Unbounded => T::MIN,
         };
         let high = match self.end {
             Bound::Included(b) => b,
-            Bound::Excluded(b) => b.checked_sub(1)?,
-            Bound::Unbounded => usize::MAX,
+            Bound::Excluded(b) => b.checked_pred()?,
+            Bound::Unbounded => T::MAX,
         };
-        (low <= high).then(|| low + (high - low) / 2)
+        (low <= high).then(|| (low, high))
     }
 
-    fn above(self, midpoint: usize) -> Self {
+    fn above(self, midpoint: T) -> Self {
         BinsearchBounds {
             start: Bound::Excluded(midpoint),
             end: self.end,
         }
     }
 
-    fn below(self, midpoint: usize) -> Self {
+    fn below(self, midpoint: T) -> Self {
         BinsearchBounds {
             start: self.start,
             end: Bound::Excluded(midpoint),
@@ -93,19 +917,14 @@ impl BinsearchBounds {
     }
 }
 
-impl<T: RangeBounds<usize>> From<T> for BinsearchBounds {
-    fn from(bounds: T) -> BinsearchBounds {
-        BinsearchBounds {
-            start: bounds.start_bound().cloned(),
-            end: bounds.end_bound().cloned(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use rstest::rstest;
+    use std::pin::pin;
+    use std::sync::Mutex;
+    use std::task::{Context, Poll, Waker};
 
     #[rstest]
     #[case(1, 16, Some(8))]
@@ -122,6 +941,8 @@ mod tests {
     #[case(1, 6, Some(5))]
     #[case(6, 16, Some(6))]
     #[case(9, 16, None)]
+    #[case(0, 8, Some(6))]
+    #[case(3, 8, Some(6))]
     fn test_last_in_range(#[case] low: usize, #[case] high: usize, #[case] answer: Option<usize>) {
         assert_eq!(last_in_range(low..high, |x| x < 7), answer);
     }
@@ -140,6 +961,354 @@ mod tests {
         #[case] high: Bound<usize>,
         #[case] mid: Option<usize>,
     ) {
-        assert_eq!(BinsearchBounds::from((low, high)).midpoint(), mid);
+        assert_eq!(BinsearchBounds::new((low, high)).midpoint(), mid);
+    }
+
+    #[test]
+    fn test_signed_spanning_zero() {
+        for threshold in i8::MIN..=i8::MAX {
+            assert_eq!(first_in_range(.., |x: i8| x >= threshold), Some(threshold));
+            assert_eq!(last_in_range(.., |x: i8| x <= threshold), Some(threshold));
+        }
+        assert_eq!(first_in_range(-10..10, |x| x >= -3), Some(-3));
+        assert_eq!(last_in_range(-10..10, |x| x < -3), Some(-4));
+        assert_eq!(first_in_range(-10..10, |x| x >= 10), None);
+    }
+
+    #[rstest]
+    #[case(i64::MIN)]
+    #[case(-1)]
+    #[case(0)]
+    #[case(1)]
+    #[case(i64::MAX)]
+    fn test_full_i64_range(#[case] threshold: i64) {
+        assert_eq!(
+            first_in_range(i64::MIN..=i64::MAX, |x| x >= threshold),
+            Some(threshold)
+        );
+        assert_eq!(
+            last_in_range(i64::MIN..=i64::MAX, |x| x <= threshold),
+            Some(threshold)
+        );
+    }
+
+    #[test]
+    fn test_u128_extremes() {
+        assert_eq!(
+            first_in_range(.., |x: u128| x == u128::MAX),
+            Some(u128::MAX)
+        );
+        assert_eq!(last_in_range(.., |x: u128| x == 0), Some(0));
+    }
+
+    #[test]
+    fn test_floor_midpoint() {
+        assert_eq!((-3i32).floor_midpoint(0), -2);
+        assert_eq!((-1i32).floor_midpoint(0), -1);
+        assert_eq!(i8::MIN.floor_midpoint(i8::MAX), -1);
+        assert_eq!(u8::MAX.floor_midpoint(u8::MAX - 2), u8::MAX - 1);
+    }
+
+    #[rstest]
+    #[case(-0.0)]
+    #[case(0.0)]
+    #[case(-1.5)]
+    #[case(f64::MIN_POSITIVE)]
+    #[case(1e300)]
+    #[case(f64::NEG_INFINITY)]
+    fn test_float_key(#[case] x: f64) {
+        assert_eq!(float_from_key(float_key(x)).to_bits(), x.to_bits());
+        assert!(float_key(x) < float_key(x.next_up()));
+    }
+
+    #[test]
+    fn test_f64_exact() {
+        let x = first_in_range_f64(-10.0..=10.0, FloatPrecision::Exact, |x| x >= -0.3);
+        assert_eq!(x, Some(-0.3));
+        let x = last_in_range_f64(-10.0..=10.0, FloatPrecision::Exact, |x| x < 2.5);
+        assert_eq!(x, Some(2.5f64.next_down()));
+        let x = first_in_range_f64(
+            f64::NEG_INFINITY..=f64::INFINITY,
+            FloatPrecision::Exact,
+            |x| x * x * x >= 2.0,
+        );
+        let x = x.unwrap();
+        assert!(x * x * x >= 2.0, "{x}^3 should be at least 2");
+        assert!(
+            x.next_down().powi(3) < 2.0,
+            "{x} should be the least value whose cube is at least 2"
+        );
+    }
+
+    #[test]
+    fn test_f64_tolerance() {
+        let x = first_in_range_f64(0.0..=2.0, FloatPrecision::Tolerance(1e-12), |x| {
+            x * x >= 2.0
+        })
+        .unwrap();
+        assert!(x * x >= 2.0, "{x}^2 should be at least 2");
+        assert!(
+            (x - std::f64::consts::SQRT_2).abs() <= 1e-12,
+            "{x} should be within tolerance of sqrt(2)"
+        );
+        let x =
+            last_in_range_f64(-5.0..=5.0, FloatPrecision::Tolerance(0.01), |x| x < -1.0).unwrap();
+        assert!((-1.01..-1.0).contains(&x), "{x} should be just below -1");
+        // A tolerance of zero bisects until the interval can shrink no
+        // further.
+        let x = first_in_range_f64(0.0..=1.0, FloatPrecision::Tolerance(0.0), |x| x >= 0.1);
+        assert_eq!(x, Some(0.1));
+    }
+
+    #[rstest]
+    #[case(f64::NAN, 1.0)]
+    #[case(0.0, f64::NAN)]
+    #[case(1.0, 0.0)]
+    fn test_f64_invalid_range(#[case] low: f64, #[case] high: f64) {
+        for precision in [FloatPrecision::Exact, FloatPrecision::Tolerance(0.1)] {
+            assert_eq!(first_in_range_f64(low..=high, precision, |_| true), None);
+            assert_eq!(last_in_range_f64(low..=high, precision, |_| true), None);
+        }
+    }
+
+    #[test]
+    fn test_f64_not_found() {
+        assert_eq!(
+            first_in_range_f64(0.0..=1.0, FloatPrecision::Exact, |x| x > 1.0),
+            None
+        );
+        assert_eq!(
+            last_in_range_f64(0.0..=1.0, FloatPrecision::Tolerance(0.1), |x| x < 0.0),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "bounds must be finite")]
+    fn test_f64_tolerance_infinite() {
+        let _ = first_in_range_f64(0.0..=f64::INFINITY, FloatPrecision::Tolerance(0.1), |x| {
+            x > 1.0
+        });
+    }
+
+    #[test]
+    fn test_gallop_evaluations() {
+        let outcome = first_in_range_with(.., SearchStrategy::Gallop, |x: u64| x >= 100);
+        assert_eq!(outcome.found, Some(100));
+        assert!(outcome.evaluations <= 16, "{outcome:?}");
+        let outcome = first_in_range_with(.., SearchStrategy::Bisect, |x: u64| x >= 100);
+        assert_eq!(outcome.found, Some(100));
+        assert!(outcome.evaluations > 64, "{outcome:?}");
+        let outcome = last_in_range_with(.., SearchStrategy::Gallop, |x: u64| x < 100);
+        assert_eq!(outcome.found, Some(99));
+        assert!(outcome.evaluations <= 16, "{outcome:?}");
+    }
+
+    #[rstest]
+    #[case(SearchStrategy::Bisect)]
+    #[case(SearchStrategy::Gallop)]
+    fn test_strategy_edges(#[case] strategy: SearchStrategy) {
+        let f = |outcome: SearchOutcome<i8>| outcome.found;
+        assert_eq!(
+            f(first_in_range_with(.., strategy, |_| true)),
+            Some(i8::MIN)
+        );
+        assert_eq!(
+            f(first_in_range_with(.., strategy, |x| x == i8::MAX)),
+            Some(i8::MAX)
+        );
+        assert_eq!(f(first_in_range_with(.., strategy, |_| false)), None);
+        assert_eq!(f(first_in_range_with(5..5, strategy, |_| true)), None);
+        assert_eq!(f(last_in_range_with(.., strategy, |_| true)), Some(i8::MAX));
+        assert_eq!(
+            f(last_in_range_with(.., strategy, |x| x == i8::MIN)),
+            Some(i8::MIN)
+        );
+        assert_eq!(f(last_in_range_with(.., strategy, |_| false)), None);
+        assert_eq!(f(last_in_range_with(-3..=-3, strategy, |_| true)), Some(-3));
+    }
+
+    #[test]
+    fn test_try_in_range() {
+        assert_eq!(
+            try_first_in_range(1..16, |x| Ok::<_, ()>(x > 7)),
+            Ok(Some(8))
+        );
+        assert_eq!(
+            try_last_in_range(1..16, |x| Ok::<_, ()>(x < 7)),
+            Ok(Some(6))
+        );
+        assert_eq!(try_first_in_range(1..5, |x| Ok::<_, ()>(x > 7)), Ok(None));
+        let r = try_first_in_range(0..100, |x| if x < 30 { Err(x) } else { Ok(x >= 40) });
+        assert_eq!(r, Err(24));
+        assert_eq!(try_last_in_range(0..100, Err::<bool, _>), Err(49));
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(r) = fut.as_mut().poll(&mut cx) {
+                return r;
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(1, 16, Some(8), Some(7))]
+    #[case(9, 16, Some(9), None)]
+    #[case(1, 8, None, Some(7))]
+    fn test_in_range_async(
+        #[case] low: u32,
+        #[case] high: u32,
+        #[case] first: Option<u32>,
+        #[case] last: Option<u32>,
+    ) {
+        assert_eq!(
+            block_on(first_in_range_async(low..high, |x| async move { x > 7 })),
+            first
+        );
+        assert_eq!(
+            block_on(last_in_range_async(low..high, |x| async move { x <= 7 })),
+            last
+        );
+    }
+
+    #[test]
+    fn test_skipping() {
+        let r = first_in_range_skipping(0..100, |x| (x % 2 == 0).then_some(x >= 41));
+        assert_eq!(r, Some(41..=42));
+        let r = last_in_range_skipping(0..100, |x| (x % 2 == 0).then_some(x <= 41));
+        assert_eq!(r, Some(40..=41));
+        let r = first_in_range_skipping(0..100, |x| (x < 90).then_some(false));
+        assert_eq!(r, None);
+        let r = first_in_range_skipping(.., |x: i8| (x != -1).then_some(x >= 0));
+        assert_eq!(r, Some(-1..=0));
+        let r = first_in_range_skipping(.., |_: i8| Some(true));
+        assert_eq!(r, Some(i8::MIN..=i8::MIN));
+        let r = last_in_range_skipping(.., |_: i8| Some(true));
+        assert_eq!(r, Some(i8::MAX..=i8::MAX));
+        let r = first_in_range_skipping(0..100, |_| None);
+        assert_eq!(r, None);
+    }
+
+    #[test]
+    fn test_memoize() {
+        let mut calls = 0;
+        let mut pred = memoize(|x: u32| {
+            calls += 1;
+            x > 7
+        });
+        assert!(!pred(3));
+        assert!(pred(9));
+        assert!(!pred(3));
+        assert!(pred(9));
+        drop(pred);
+        assert_eq!(calls, 2);
+    }
+
+    #[rstest]
+    #[case(0, 0, 3, vec![0])]
+    #[case(0, 9, 1, vec![4])]
+    #[case(0, 9, 3, vec![1, 4, 7])]
+    #[case(0, 2, 5, vec![0, 1, 2])]
+    #[case(-8, 7, 3, vec![-5, -1, 3])]
+    fn test_probe_points(
+        #[case] a: i32,
+        #[case] b: i32,
+        #[case] k: usize,
+        #[case] points: Vec<i32>,
+    ) {
+        assert_eq!(probe_points(a, b, k), points);
+    }
+
+    #[test]
+    fn test_par_in_range_full() {
+        let probes = NonZeroUsize::new(7).unwrap();
+        for threshold in [i64::MIN, -1, 0, 1, i64::MAX] {
+            assert_eq!(
+                par_first_in_range(.., probes, |x| x >= threshold),
+                Some(threshold)
+            );
+            assert_eq!(
+                par_last_in_range(.., probes, |x| x <= threshold),
+                Some(threshold)
+            );
+        }
+        assert_eq!(par_first_in_range(.., probes, |_: u8| false), None);
+        assert_eq!(par_last_in_range(.., probes, |_: u8| false), None);
+        assert_eq!(par_first_in_range(5..5, probes, |_| true), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_gallop_matches_bisect(
+            start in any::<i16>(),
+            len in 0u16..1000,
+            threshold in any::<i16>(),
+        ) {
+            let end = start.saturating_add_unsigned(len);
+            for strategy in [SearchStrategy::Bisect, SearchStrategy::Gallop] {
+                prop_assert_eq!(
+                    first_in_range_with(start..end, strategy, |x| x >= threshold).found,
+                    first_in_range(start..end, |x| x >= threshold)
+                );
+                prop_assert_eq!(
+                    last_in_range_with(start..end, strategy, |x| x < threshold).found,
+                    last_in_range(start..end, |x| x < threshold)
+                );
+            }
+        }
+
+        #[test]
+        fn prop_skipping(
+            threshold in 0u8..=64,
+            skips in prop::collection::btree_set(0u8..64, 0..40),
+        ) {
+            let mut seen = BTreeSet::new();
+            let r = first_in_range_skipping(0..64, |x| {
+                assert!(seen.insert(x), "{x} evaluated twice");
+                (!skips.contains(&x)).then_some(x >= threshold)
+            });
+            if let Some(r) = r {
+                prop_assert!(r.contains(&threshold));
+                prop_assert!((*r.start()..*r.end()).all(|x| skips.contains(&x)));
+                prop_assert!(!skips.contains(r.end()));
+            } else {
+                prop_assert!((threshold..64).all(|x| skips.contains(&x)));
+            }
+
+            let mut seen = BTreeSet::new();
+            let r = last_in_range_skipping(0..64, |x| {
+                assert!(seen.insert(x), "{x} evaluated twice");
+                (!skips.contains(&x)).then_some(x < threshold)
+            });
+            if let Some(r) = r {
+                prop_assert!(r.contains(&(threshold - 1)));
+                prop_assert!((*r.start() + 1..=*r.end()).all(|x| skips.contains(&x)));
+                prop_assert!(!skips.contains(r.start()));
+            } else {
+                prop_assert!((0..threshold).all(|x| skips.contains(&x)));
+            }
+        }
+
+        #[test]
+        fn prop_par_in_range(
+            start in 0u16..1000,
+            len in 0u16..1000,
+            threshold in 0u16..2000,
+            probes in 1usize..10,
+        ) {
+            let probes = NonZeroUsize::new(probes).unwrap();
+            let end = start + len;
+            let seen = Mutex::new(BTreeSet::new());
+            let r = par_first_in_range(start..end, probes, |x| {
+                assert!(seen.lock().unwrap().insert(x), "{x} evaluated twice");
+                x >= threshold
+            });
+            prop_assert_eq!(r, first_in_range(start..end, |x| x >= threshold));
+            let r = par_last_in_range(start..end, probes, |x| x < threshold);
+            prop_assert_eq!(r, last_in_range(start..end, |x| x < threshold));
+        }
     }
 }
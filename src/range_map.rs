@@ -0,0 +1,333 @@
+use crate::cmp_range::{RangeOrdering, try_cmp_range};
+use crate::range_set::RangeSet;
+use std::collections::btree_map::{self, BTreeMap};
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// A map from disjoint half-open ranges of keys of type `T` to values of type
+/// `V`
+///
+/// Inserting a range overwrites the values for any keys in that range that
+/// were already present, trimming or splitting the existing ranges as
+/// necessary.  Adjacent ranges are not merged, even if their values are equal.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct RangeMap<T, V> {
+    // Maps the start of each range to its end and value
+    entries: BTreeMap<T, (T, V)>,
+}
+
+impl<T, V> RangeMap<T, V> {
+    /// Create a new, empty `RangeMap`
+    pub fn new() -> RangeMap<T, V> {
+        RangeMap {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of ranges in the map
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<T: Clone, V> RangeMap<T, V> {
+    /// Returns an iterator over the ranges in the map and their values, in
+    /// ascending order
+    pub fn iter(&self) -> Iter<'_, T, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+}
+
+impl<T: Ord + Clone, V> RangeMap<T, V> {
+    /// Returns the value for the range containing `key`, if any
+    pub fn get(&self, key: &T) -> Option<&V> {
+        self.get_range(key).map(|(_, _, v)| v)
+    }
+
+    /// Returns the range containing `key` and its value, along with where
+    /// `key` falls in that range (either [`RangeOrdering::EqLower`] or
+    /// [`RangeOrdering::Between`]), or `None` if no range contains `key`
+    pub fn get_range(&self, key: &T) -> Option<(Range<T>, RangeOrdering, &V)> {
+        let (s, (e, v)) = self.entries.range(..=key).next_back()?;
+        let ord = try_cmp_range(key, s..e)?;
+        ord.is_within().then(|| (s.clone()..e.clone(), ord, v))
+    }
+
+    /// Returns `true` if some range in the map contains `key`
+    pub fn contains_key(&self, key: &T) -> bool {
+        self.get_range(key).is_some()
+    }
+
+    /// Returns an iterator over the ranges in the map that overlap `range`,
+    /// along with their values, in ascending order.  The ranges are yielded
+    /// whole, not clipped to `range`.
+    pub fn overlapping(&self, range: Range<T>) -> impl Iterator<Item = (Range<T>, &V)> + '_ {
+        let start = if range.is_empty() {
+            None
+        } else {
+            match self.entries.range(..&range.start).next_back() {
+                Some((s, (e, _))) if *e > range.start => Some(s.clone()),
+                _ => Some(range.start.clone()),
+            }
+        };
+        let end = range.end;
+        start
+            .into_iter()
+            .flat_map(move |start| self.entries.range(start..))
+            .take_while(move |(s, _)| **s < end)
+            .map(|(s, (e, v))| (s.clone()..e.clone(), v))
+    }
+
+    /// Returns the ranges of keys within `range` that are not in the map
+    pub fn gaps(&self, range: Range<T>) -> RangeSet<T> {
+        let mut gaps = RangeSet::new();
+        gaps.insert(range.clone());
+        for (r, _) in self.overlapping(range) {
+            gaps.remove(r);
+        }
+        gaps
+    }
+}
+
+impl<T: Ord + Clone, V: Clone> RangeMap<T, V> {
+    /// Map all keys in `range` to `value`, overwriting any existing values
+    /// for those keys.  Existing ranges that extend past either end of
+    /// `range` are trimmed or split.  Empty ranges are ignored.
+    pub fn insert(&mut self, range: Range<T>, value: V) {
+        if range.is_empty() {
+            return;
+        }
+        self.remove(range.clone());
+        self.entries.insert(range.start, (range.end, value));
+    }
+
+    /// Remove all keys in `range` from the map.  Existing ranges that extend
+    /// past either end of `range` are trimmed or split.
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        if let Some((_, (e, v))) = self.entries.range_mut(..&range.start).next_back()
+            && *e > range.start
+        {
+            let tail = std::mem::replace(e, range.start.clone());
+            if tail > range.end {
+                let v = v.clone();
+                self.entries.insert(range.end.clone(), (tail, v));
+            }
+        }
+        let removed = self
+            .entries
+            .range(&range.start..&range.end)
+            .map(|(s, _)| s.clone())
+            .collect::<Vec<_>>();
+        for s in removed {
+            if let Some((e, v)) = self.entries.remove(&s)
+                && e > range.end
+            {
+                self.entries.insert(range.end.clone(), (e, v));
+            }
+        }
+    }
+}
+
+impl<T, V> Default for RangeMap<T, V> {
+    fn default() -> RangeMap<T, V> {
+        RangeMap::new()
+    }
+}
+
+impl<T: fmt::Debug, V: fmt::Debug> fmt::Debug for RangeMap<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(s, (e, v))| (s..e, v)))
+            .finish()
+    }
+}
+
+impl<T: Ord + Clone, V: Clone> Extend<(Range<T>, V)> for RangeMap<T, V> {
+    fn extend<I: IntoIterator<Item = (Range<T>, V)>>(&mut self, iter: I) {
+        for (r, v) in iter {
+            self.insert(r, v);
+        }
+    }
+}
+
+impl<T: Ord + Clone, V: Clone> FromIterator<(Range<T>, V)> for RangeMap<T, V> {
+    fn from_iter<I: IntoIterator<Item = (Range<T>, V)>>(iter: I) -> RangeMap<T, V> {
+        let mut map = RangeMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<T: Ord + Clone, V: Clone, const N: usize> From<[(Range<T>, V); N]> for RangeMap<T, V> {
+    fn from(entries: [(Range<T>, V); N]) -> RangeMap<T, V> {
+        RangeMap::from_iter(entries)
+    }
+}
+
+impl<'a, T: Clone, V> IntoIterator for &'a RangeMap<T, V> {
+    type Item = (Range<T>, &'a V);
+    type IntoIter = Iter<'a, T, V>;
+
+    fn into_iter(self) -> Iter<'a, T, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the ranges & values in a [`RangeMap`]
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T, V> {
+    inner: btree_map::Iter<'a, T, (T, V)>,
+}
+
+impl<'a, T: Clone, V> Iterator for Iter<'a, T, V> {
+    type Item = (Range<T>, &'a V);
+
+    fn next(&mut self) -> Option<(Range<T>, &'a V)> {
+        self.inner
+            .next()
+            .map(|(s, (e, v))| (s.clone()..e.clone(), v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T: Clone, V> DoubleEndedIterator for Iter<'a, T, V> {
+    fn next_back(&mut self) -> Option<(Range<T>, &'a V)> {
+        self.inner
+            .next_back()
+            .map(|(s, (e, v))| (s.clone()..e.clone(), v))
+    }
+}
+
+impl<T: Clone, V> ExactSizeIterator for Iter<'_, T, V> {}
+
+impl<T: Clone, V> FusedIterator for Iter<'_, T, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+    use std::collections::BTreeMap;
+
+    fn entries(map: &RangeMap<u32, char>) -> Vec<(Range<u32>, char)> {
+        map.iter().map(|(r, &v)| (r, v)).collect()
+    }
+
+    #[rstest]
+    #[case(0..2, vec![(0..2, 'x'), (2..5, 'a'), (10..15, 'b')])]
+    #[case(0..3, vec![(0..3, 'x'), (3..5, 'a'), (10..15, 'b')])]
+    #[case(3..4, vec![(1..3, 'a'), (3..4, 'x'), (4..5, 'a'), (10..15, 'b')])]
+    #[case(4..12, vec![(1..4, 'a'), (4..12, 'x'), (12..15, 'b')])]
+    #[case(5..10, vec![(1..5, 'a'), (5..10, 'x'), (10..15, 'b')])]
+    #[case(0..20, vec![(0..20, 'x')])]
+    #[case(10..15, vec![(1..5, 'a'), (10..15, 'x')])]
+    #[case(7..7, vec![(1..5, 'a'), (10..15, 'b')])]
+    fn test_insert(#[case] range: Range<u32>, #[case] expected: Vec<(Range<u32>, char)>) {
+        let mut map = RangeMap::from([(1..5, 'a'), (10..15, 'b')]);
+        map.insert(range, 'x');
+        assert_eq!(entries(&map), expected);
+    }
+
+    #[rstest]
+    #[case(0..2, vec![(2..5, 'a'), (10..15, 'b')])]
+    #[case(3..4, vec![(1..3, 'a'), (4..5, 'a'), (10..15, 'b')])]
+    #[case(4..12, vec![(1..4, 'a'), (12..15, 'b')])]
+    #[case(0..20, vec![])]
+    fn test_remove(#[case] range: Range<u32>, #[case] expected: Vec<(Range<u32>, char)>) {
+        let mut map = RangeMap::from([(1..5, 'a'), (10..15, 'b')]);
+        map.remove(range);
+        assert_eq!(entries(&map), expected);
+    }
+
+    #[test]
+    fn test_get() {
+        let map = RangeMap::from([(1..5, 'a'), (5..8, 'b'), (10..15, 'c')]);
+        assert_eq!(map.get(&0), None);
+        assert_eq!(map.get(&4), Some(&'a'));
+        assert_eq!(map.get(&5), Some(&'b'));
+        assert_eq!(map.get(&8), None);
+        assert_eq!(
+            map.get_range(&5),
+            Some((5..8, RangeOrdering::EqLower, &'b'))
+        );
+        assert_eq!(
+            map.get_range(&12),
+            Some((10..15, RangeOrdering::Between, &'c'))
+        );
+        assert!(map.contains_key(&14));
+        assert!(!map.contains_key(&15));
+    }
+
+    #[test]
+    fn test_overlapping_and_gaps() {
+        let map = RangeMap::from([(1..5, 'a'), (5..8, 'b'), (10..15, 'c')]);
+        assert_eq!(
+            map.overlapping(4..11).collect::<Vec<_>>(),
+            [(1..5, &'a'), (5..8, &'b'), (10..15, &'c')]
+        );
+        assert_eq!(map.overlapping(8..10).count(), 0);
+        assert_eq!(
+            map.gaps(0..20).iter().collect::<Vec<_>>(),
+            [0..1, 8..10, 15..20]
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        let map = RangeMap::from([(1..5, 'a'), (10..15, 'b')]);
+        assert_eq!(format!("{map:?}"), "{1..5: 'a', 10..15: 'b'}");
+    }
+
+    fn model(map: &RangeMap<u8, u8>) -> BTreeMap<u8, u8> {
+        map.iter()
+            .flat_map(|(r, &v)| r.map(move |k| (k, v)))
+            .collect()
+    }
+
+    fn arb_range() -> impl Strategy<Value = Range<u8>> {
+        (0u8..64, 0u8..16).prop_map(|(s, n)| s..(s + n))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_operations(ops in prop::collection::vec((arb_range(), any::<Option<u8>>()), 0..10)) {
+            let mut map = RangeMap::new();
+            let mut expected = BTreeMap::new();
+            for (range, value) in ops {
+                if let Some(v) = value {
+                    map.insert(range.clone(), v);
+                    expected.extend(range.map(|k| (k, v)));
+                } else {
+                    map.remove(range.clone());
+                    for k in range {
+                        expected.remove(&k);
+                    }
+                }
+                let rs = map.iter().map(|(r, _)| r).collect::<Vec<_>>();
+                prop_assert!(rs.iter().all(|r| !r.is_empty()));
+                prop_assert!(rs.iter().zip(rs.iter().skip(1)).all(|(a, b)| a.end <= b.start));
+                prop_assert_eq!(model(&map), expected.clone());
+            }
+            for k in 0u8..100 {
+                prop_assert_eq!(map.get(&k), expected.get(&k));
+            }
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use crate::cmp_range::{RangeOrdering, try_cmp_range};
+use std::collections::btree_map::{self, BTreeMap};
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// A set of values of type `T` stored as a collection of disjoint half-open
+/// ranges
+///
+/// Overlapping and adjacent ranges (e.g., `1..3` and `3..5`) are merged when
+/// inserted, so the ranges in a `RangeSet` are always nonempty, disjoint,
+/// non-adjacent, and iterated over in ascending order.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct RangeSet<T> {
+    // Maps the start of each range to its end
+    ranges: BTreeMap<T, T>,
+}
+
+impl<T> RangeSet<T> {
+    /// Create a new, empty `RangeSet`
+    pub fn new() -> RangeSet<T> {
+        RangeSet {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of disjoint ranges in the set
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+}
+
+impl<T: Clone> RangeSet<T> {
+    /// Returns an iterator over the ranges in the set in ascending order
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.ranges.iter(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> RangeSet<T> {
+    /// Add the values in `range` to the set, merging it with any ranges that
+    /// it overlaps or is adjacent to.  Empty ranges are ignored.
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        let Range { mut start, mut end } = range;
+        if let Some((s, e)) = self.ranges.range(..&start).next_back()
+            && *e >= start
+        {
+            start = s.clone();
+            if *e > end {
+                end = e.clone();
+            }
+        }
+        let absorbed = self
+            .ranges
+            .range(&start..=&end)
+            .map(|(s, _)| s.clone())
+            .collect::<Vec<_>>();
+        for s in absorbed {
+            if let Some(e) = self.ranges.remove(&s)
+                && e > end
+            {
+                end = e;
+            }
+        }
+        self.ranges.insert(start, end);
+    }
+
+    /// Remove the values in `range` from the set, splitting any ranges that
+    /// extend past either end of it
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        if let Some((s, e)) = self.ranges.range(..&range.start).next_back()
+            && *e > range.start
+        {
+            let (s, e) = (s.clone(), e.clone());
+            if e > range.end {
+                self.ranges.insert(range.end.clone(), e);
+            }
+            self.ranges.insert(s, range.start.clone());
+        }
+        let removed = self
+            .ranges
+            .range(&range.start..&range.end)
+            .map(|(s, _)| s.clone())
+            .collect::<Vec<_>>();
+        for s in removed {
+            if let Some(e) = self.ranges.remove(&s)
+                && e > range.end
+            {
+                self.ranges.insert(range.end.clone(), e);
+            }
+        }
+    }
+
+    /// Returns `true` if `value` is in the set
+    pub fn contains(&self, value: &T) -> bool {
+        self.get(value).is_some()
+    }
+
+    /// Returns the range in the set containing `value`, along with where
+    /// `value` falls in that range (either [`RangeOrdering::EqLower`] or
+    /// [`RangeOrdering::Between`]), or `None` if `value` is not in the set
+    pub fn get(&self, value: &T) -> Option<(Range<T>, RangeOrdering)> {
+        let (s, e) = self.ranges.range(..=value).next_back()?;
+        let ord = try_cmp_range(value, s..e)?;
+        ord.is_within().then(|| (s.clone()..e.clone(), ord))
+    }
+
+    /// Returns `true` if any values in `range` are in the set
+    pub fn overlaps(&self, range: Range<T>) -> bool {
+        self.overlapping(range).next().is_some()
+    }
+
+    /// Returns `true` if all values in `range` are in the set
+    pub fn covers(&self, range: Range<T>) -> bool {
+        range.is_empty()
+            || self
+                .get(&range.start)
+                .is_some_and(|(r, _)| r.end >= range.end)
+    }
+
+    /// Returns an iterator over the ranges in the set that overlap `range`, in
+    /// ascending order.  The ranges are yielded whole, not clipped to
+    /// `range`.
+    pub fn overlapping(&self, range: Range<T>) -> impl Iterator<Item = Range<T>> + '_ {
+        let start = if range.is_empty() {
+            None
+        } else {
+            match self.ranges.range(..&range.start).next_back() {
+                Some((s, e)) if *e > range.start => Some(s.clone()),
+                _ => Some(range.start.clone()),
+            }
+        };
+        let end = range.end;
+        start
+            .into_iter()
+            .flat_map(move |start| self.ranges.range(start..))
+            .take_while(move |(s, _)| **s < end)
+            .map(|(s, e)| s.clone()..e.clone())
+    }
+
+    /// Returns the ranges of values within `range` that are not in the set
+    pub fn gaps(&self, range: Range<T>) -> RangeSet<T> {
+        let mut gaps = RangeSet::new();
+        gaps.insert(range);
+        gaps.difference(self)
+    }
+
+    /// Returns the set of values that are in `self`, `other`, or both
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut set = self.clone();
+        set.extend(other.iter());
+        set
+    }
+
+    /// Returns the set of values that are in both `self` and `other`
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut set = RangeSet::new();
+        let mut left = self.ranges.iter().peekable();
+        let mut right = other.ranges.iter().peekable();
+        while let (Some(&(s1, e1)), Some(&(s2, e2))) = (left.peek(), right.peek()) {
+            let start = s1.max(s2);
+            let end = e1.min(e2);
+            if start < end {
+                set.ranges.insert(start.clone(), end.clone());
+            }
+            if e1 < e2 {
+                left.next();
+            } else {
+                right.next();
+            }
+        }
+        set
+    }
+
+    /// Returns the set of values that are in `self` but not `other`
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut set = self.clone();
+        for r in other {
+            set.remove(r);
+        }
+        set
+    }
+}
+
+impl<T> Default for RangeSet<T> {
+    fn default() -> RangeSet<T> {
+        RangeSet::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.ranges.iter().map(|(s, e)| s..e))
+            .finish()
+    }
+}
+
+impl<T: Ord + Clone> Extend<Range<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for r in iter {
+            self.insert(r);
+        }
+    }
+}
+
+impl<T: Ord + Clone> FromIterator<Range<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> RangeSet<T> {
+        let mut set = RangeSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Ord + Clone, const N: usize> From<[Range<T>; N]> for RangeSet<T> {
+    fn from(ranges: [Range<T>; N]) -> RangeSet<T> {
+        RangeSet::from_iter(ranges)
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a RangeSet<T> {
+    type Item = Range<T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Iterator over the ranges in a [`RangeSet`]
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    inner: btree_map::Iter<'a, T, T>,
+}
+
+impl<T: Clone> Iterator for Iter<'_, T> {
+    type Item = Range<T>;
+
+    fn next(&mut self) -> Option<Range<T>> {
+        self.inner.next().map(|(s, e)| s.clone()..e.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Range<T>> {
+        self.inner.next_back().map(|(s, e)| s.clone()..e.clone())
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T: Clone> FusedIterator for Iter<'_, T> {}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rstest::rstest;
+    use std::collections::BTreeSet;
+
+    fn ranges(set: &RangeSet<u32>) -> Vec<Range<u32>> {
+        set.iter().collect()
+    }
+
+    #[rstest]
+    #[case(&[1..3, 5..7], vec![1..3, 5..7])]
+    #[case(&[5..7, 1..3], vec![1..3, 5..7])]
+    #[case(&[1..3, 3..5], vec![1..5])]
+    #[case(&[3..5, 1..3], vec![1..5])]
+    #[case(&[1..4, 2..6], vec![1..6])]
+    #[case(&[1..10, 2..6], vec![1..10])]
+    #[case(&[2..6, 1..10], vec![1..10])]
+    #[case(&[1..2, 4..5, 7..8, 2..7], vec![1..8])]
+    #[case(&[1..2, 4..5, 7..8, 3..6], vec![1..2, 3..6, 7..8])]
+    #[case(&[1..2, 3..3, 5..4], vec![1..2])]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn test_insert(#[case] inserted: &[Range<u32>], #[case] expected: Vec<Range<u32>>) {
+        let set = inserted.iter().cloned().collect::<RangeSet<_>>();
+        assert_eq!(ranges(&set), expected);
+    }
+
+    #[rstest]
+    #[case(0..1, vec![1..5, 10..15])]
+    #[case(0..2, vec![2..5, 10..15])]
+    #[case(2..4, vec![1..2, 4..5, 10..15])]
+    #[case(4..12, vec![1..4, 12..15])]
+    #[case(5..10, vec![1..5, 10..15])]
+    #[case(0..20, vec![])]
+    #[case(1..15, vec![])]
+    #[case(12..13, vec![1..5, 10..12, 13..15])]
+    fn test_remove(#[case] removed: Range<u32>, #[case] expected: Vec<Range<u32>>) {
+        let mut set = RangeSet::from([1..5, 10..15]);
+        set.remove(removed);
+        assert_eq!(ranges(&set), expected);
+    }
+
+    #[test]
+    fn test_get() {
+        let set = RangeSet::from([1..5, 10..15]);
+        assert_eq!(set.get(&0), None);
+        assert_eq!(set.get(&1), Some((1..5, RangeOrdering::EqLower)));
+        assert_eq!(set.get(&3), Some((1..5, RangeOrdering::Between)));
+        assert_eq!(set.get(&5), None);
+        assert_eq!(set.get(&14), Some((10..15, RangeOrdering::Between)));
+        assert_eq!(set.get(&15), None);
+        assert!(set.contains(&10));
+        assert!(!set.contains(&9));
+    }
+
+    #[test]
+    fn test_range_queries() {
+        let set = RangeSet::from([1..5, 10..15, 20..25]);
+        assert_eq!(set.overlapping(3..12).collect::<Vec<_>>(), [1..5, 10..15]);
+        assert_eq!(set.overlapping(5..10).count(), 0);
+        assert_eq!(set.overlapping(0..100).count(), 3);
+        assert_eq!(set.overlapping(3..3).count(), 0);
+        assert!(set.overlaps(4..6));
+        assert!(!set.overlaps(15..20));
+        assert!(set.covers(10..15));
+        assert!(set.covers(11..13));
+        assert!(!set.covers(9..13));
+        assert!(!set.covers(12..16));
+        assert!(set.covers(7..7));
+        assert_eq!(ranges(&set.gaps(0..22)), [0..1, 5..10, 15..20]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = RangeSet::from([1..5, 10..15]);
+        let b = RangeSet::from([3..12, 14..20]);
+        assert_eq!(ranges(&a.union(&b)), [1..20]);
+        assert_eq!(ranges(&a.intersection(&b)), [3..5, 10..12, 14..15]);
+        assert_eq!(ranges(&a.difference(&b)), [1..3, 12..14]);
+        assert_eq!(ranges(&b.difference(&a)), [5..10, 15..20]);
+    }
+
+    #[test]
+    fn test_debug() {
+        let set = RangeSet::from([1..5, 10..15]);
+        assert_eq!(format!("{set:?}"), "{1..5, 10..15}");
+    }
+
+    fn model(set: &RangeSet<u8>) -> BTreeSet<u8> {
+        set.iter().flatten().collect()
+    }
+
+    fn model_of(rs: &[Range<u8>]) -> BTreeSet<u8> {
+        rs.iter().cloned().flatten().collect()
+    }
+
+    fn arb_ranges() -> impl Strategy<Value = Vec<Range<u8>>> {
+        prop::collection::vec((0u8..64, 0u8..16).prop_map(|(s, n)| s..(s + n)), 0..8)
+    }
+
+    fn is_normalized(set: &RangeSet<u8>) -> bool {
+        let rs = set.iter().collect::<Vec<_>>();
+        rs.iter().all(|r| !r.is_empty())
+            && rs
+                .iter()
+                .zip(rs.iter().skip(1))
+                .all(|(a, b)| a.end < b.start)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_insert(rs in arb_ranges()) {
+            let set = rs.iter().cloned().collect::<RangeSet<_>>();
+            prop_assert!(is_normalized(&set));
+            prop_assert_eq!(model(&set), model_of(&rs));
+        }
+
+        #[test]
+        fn prop_remove(rs in arb_ranges(), removed in arb_ranges()) {
+            let mut set = rs.iter().cloned().collect::<RangeSet<_>>();
+            for r in removed.clone() {
+                set.remove(r);
+            }
+            prop_assert!(is_normalized(&set));
+            let expected = model_of(&rs).difference(&model_of(&removed)).copied().collect::<BTreeSet<_>>();
+            prop_assert_eq!(model(&set), expected);
+        }
+
+        #[test]
+        fn prop_set_operations(xs in arb_ranges(), ys in arb_ranges()) {
+            let a = xs.iter().cloned().collect::<RangeSet<_>>();
+            let b = ys.iter().cloned().collect::<RangeSet<_>>();
+            let (ma, mb) = (model(&a), model(&b));
+            let union = a.union(&b);
+            let intersection = a.intersection(&b);
+            let difference = a.difference(&b);
+            prop_assert!(is_normalized(&union));
+            prop_assert!(is_normalized(&intersection));
+            prop_assert!(is_normalized(&difference));
+            prop_assert_eq!(model(&union), ma.union(&mb).copied().collect::<BTreeSet<_>>());
+            prop_assert_eq!(model(&intersection), ma.intersection(&mb).copied().collect::<BTreeSet<_>>());
+            prop_assert_eq!(model(&difference), ma.difference(&mb).copied().collect::<BTreeSet<_>>());
+        }
+
+        #[test]
+        fn prop_queries(rs in arb_ranges(), query in (0u8..80, 0u8..16).prop_map(|(s, n)| s..(s + n))) {
+            let set = rs.iter().cloned().collect::<RangeSet<_>>();
+            let m = model(&set);
+            for x in 0u8..100 {
+                prop_assert_eq!(set.contains(&x), m.contains(&x));
+            }
+            prop_assert_eq!(set.overlaps(query.clone()), query.clone().any(|x| m.contains(&x)));
+            prop_assert_eq!(set.covers(query.clone()), query.clone().all(|x| m.contains(&x)));
+            let overlapping = set.overlapping(query.clone()).collect::<Vec<_>>();
+            let expected = set.iter().filter(|r| r.start < query.end && query.start < r.end && !query.is_empty()).collect::<Vec<_>>();
+            prop_assert_eq!(overlapping, expected);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::signed_duration::SignedDuration;
 use std::fmt::{self, Write};
 use std::time::Duration;
 
@@ -6,6 +7,10 @@ pub trait DurationExt {
     /// floating-point number of seconds using no more precision than is
     /// necessary.
     ///
+    /// If a precision is given in the format string (e.g., `{:.3}`) or via
+    /// [`DisplayAsSeconds::precision()`], exactly that many fractional digits
+    /// are displayed, rounded as per [`DisplayAsSeconds::rounding()`].
+    ///
     /// # Example
     ///
     /// ```
@@ -16,38 +21,561 @@ pub trait DurationExt {
     ///
     /// let d2 = Duration::from_nanos(123_000_000);
     /// assert_eq!(d2.display_as_seconds().to_string(), "0.123");
+    ///
+    /// let d3 = Duration::from_nanos(1_999_600_000);
+    /// assert_eq!(format!("{:.3}", d3.display_as_seconds()), "2.000");
     /// ```
     fn display_as_seconds(self) -> DisplayAsSeconds;
+
+    /// Returns a structure that displays the given [`Duration`] in a compact
+    /// human-readable form consisting of days, hours, minutes, and seconds,
+    /// separated by spaces.  Components that are zero are omitted, unless the
+    /// whole duration is zero, in which case it is displayed as `"0s"`.
+    ///
+    /// By default, seconds are displayed using no more precision than is
+    /// necessary; see [`DisplayHumanized::precision()`] and
+    /// [`DisplayHumanized::rounding()`] for how to change this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib::show_duration::DurationExt;
+    /// # use std::time::Duration;
+    /// let d = Duration::from_millis(3_723_400);
+    /// assert_eq!(d.display_humanized().to_string(), "1h 2m 3.4s");
+    /// assert_eq!(format!("{:.0}", d.display_humanized()), "1h 2m 3s");
+    /// ```
+    fn display_humanized(self) -> DisplayHumanized;
+
+    /// Returns a structure that displays the given [`Duration`] as a clock
+    /// reading of the form `HH:MM:SS.fff`.  The hours field is at least two
+    /// digits wide and is not wrapped at 24.
+    ///
+    /// By default, seconds are displayed with exactly three fractional
+    /// digits; see [`DisplayClock::precision()`] and
+    /// [`DisplayClock::rounding()`] for how to change this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib::show_duration::DurationExt;
+    /// # use std::time::Duration;
+    /// let d = Duration::from_millis(3_723_400);
+    /// assert_eq!(d.display_clock().to_string(), "01:02:03.400");
+    /// assert_eq!(format!("{:.0}", d.display_clock()), "01:02:03");
+    /// ```
+    fn display_clock(self) -> DisplayClock;
+
+    /// Returns a structure that displays the given [`Duration`] as an
+    /// [ISO 8601 duration][1] of the form `PTnHnMnS`.  Components that are
+    /// zero are omitted, unless the whole duration is zero, in which case it
+    /// is displayed as `"PT0S"`.  Days are not used, as ISO 8601 days are not
+    /// necessarily 24 hours long.
+    ///
+    /// By default, seconds are displayed using no more precision than is
+    /// necessary; see [`DisplayIso8601::precision()`] and
+    /// [`DisplayIso8601::rounding()`] for how to change this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib::show_duration::DurationExt;
+    /// # use std::time::Duration;
+    /// let d = Duration::from_millis(3_723_400);
+    /// assert_eq!(d.display_iso8601().to_string(), "PT1H2M3.4S");
+    /// ```
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/ISO_8601#Durations
+    fn display_iso8601(self) -> DisplayIso8601;
+
+    /// Returns a structure that displays the given [`Duration`] in the same
+    /// format as Go's `time.Duration.String()`, e.g., `"1h2m3.4s"`.
+    /// Durations of less than a second are displayed using the smallest unit
+    /// (`ms`, `µs`, or `ns`) that keeps the integer part nonzero, e.g.,
+    /// `"1.5ms"`.
+    ///
+    /// By default, the final component is displayed using no more precision
+    /// than is necessary; see [`DisplayGo::precision()`] and
+    /// [`DisplayGo::rounding()`] for how to change this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rswodlib::show_duration::DurationExt;
+    /// # use std::time::Duration;
+    /// let d = Duration::from_millis(3_723_400);
+    /// assert_eq!(d.display_go().to_string(), "1h2m3.4s");
+    ///
+    /// let d = Duration::from_micros(1500);
+    /// assert_eq!(d.display_go().to_string(), "1.5ms");
+    /// ```
+    fn display_go(self) -> DisplayGo;
 }
 
 impl DurationExt for Duration {
     fn display_as_seconds(self) -> DisplayAsSeconds {
-        DisplayAsSeconds(self)
+        DisplayAsSeconds::new(self, false)
+    }
+
+    fn display_humanized(self) -> DisplayHumanized {
+        DisplayHumanized::new(self, false)
+    }
+
+    fn display_clock(self) -> DisplayClock {
+        DisplayClock::new(self, false).precision(3)
+    }
+
+    fn display_iso8601(self) -> DisplayIso8601 {
+        DisplayIso8601::new(self, false)
+    }
+
+    fn display_go(self) -> DisplayGo {
+        DisplayGo::new(self, false)
     }
 }
 
+/// The display adapters for a [`SignedDuration`] display its magnitude in the
+/// same way as for a [`Duration`], preceded by a minus sign if the duration
+/// is negative.  No sign is displayed if the duration rounds to zero.
+///
+/// # Example
+///
+/// ```
+/// # use rswodlib::show_duration::DurationExt;
+/// # use rswodlib::signed_duration::SignedDuration;
+/// # use std::time::Duration;
+/// let d = SignedDuration::negative(Duration::from_millis(125));
+/// assert_eq!(d.display_as_seconds().to_string(), "-0.125");
+/// assert_eq!(d.display_go().to_string(), "-125ms");
+/// assert_eq!(format!("{:.0}", d.display_as_seconds()), "0");
+/// ```
+impl DurationExt for SignedDuration {
+    fn display_as_seconds(self) -> DisplayAsSeconds {
+        DisplayAsSeconds::new(self.magnitude(), self.is_negative())
+    }
+
+    fn display_humanized(self) -> DisplayHumanized {
+        DisplayHumanized::new(self.magnitude(), self.is_negative())
+    }
+
+    fn display_clock(self) -> DisplayClock {
+        DisplayClock::new(self.magnitude(), self.is_negative()).precision(3)
+    }
+
+    fn display_iso8601(self) -> DisplayIso8601 {
+        DisplayIso8601::new(self.magnitude(), self.is_negative())
+    }
+
+    fn display_go(self) -> DisplayGo {
+        DisplayGo::new(self.magnitude(), self.is_negative())
+    }
+}
+
+/// Structure returned by [`DurationExt::display_as_seconds()`]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct DisplayAsSeconds(Duration);
+pub struct DisplayAsSeconds {
+    duration: Duration,
+    negative: bool,
+    options: FormatOptions,
+}
 
 impl fmt::Display for DisplayAsSeconds {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0.as_secs())?;
-        let nanos = self.0.subsec_nanos();
-        if nanos > 0 {
-            f.write_char('.')?;
-            let mut frac = nanos;
-            let mut divisor = 1_000_000_000 / 10;
-            while frac > 0 && divisor > 0 {
-                let d = frac / divisor;
-                f.write_char(char::from_digit(d, 10).expect("should be valid decimal digit"))?;
-                frac %= divisor;
-                divisor /= 10;
+        let secs = self
+            .options
+            .round(f, self.duration.as_nanos(), SECOND_SCALE);
+        let mut s = String::from(self.sign(&secs));
+        write!(s, "{secs}")?;
+        pad(f, &s)
+    }
+}
+
+/// How to round the fractional digits of a duration that are beyond the
+/// requested precision
+///
+/// Rounding is applied to the magnitude of a duration, so, for a negative
+/// [`SignedDuration`], "up" means away from zero.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Rounding {
+    /// Discard the excess digits
+    Truncate,
+    /// Round up if any of the excess digits are nonzero
+    Up,
+    /// Round to the nearest value, rounding up on ties
+    #[default]
+    HalfUp,
+    /// Round to the nearest value, rounding to an even final digit on ties
+    HalfEven,
+}
+
+/// Structure returned by [`DurationExt::display_humanized()`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DisplayHumanized {
+    duration: Duration,
+    negative: bool,
+    options: FormatOptions,
+}
+
+/// Structure returned by [`DurationExt::display_clock()`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DisplayClock {
+    duration: Duration,
+    negative: bool,
+    options: FormatOptions,
+}
+
+/// Structure returned by [`DurationExt::display_iso8601()`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DisplayIso8601 {
+    duration: Duration,
+    negative: bool,
+    options: FormatOptions,
+}
+
+/// Structure returned by [`DurationExt::display_go()`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DisplayGo {
+    duration: Duration,
+    negative: bool,
+    options: FormatOptions,
+}
+
+// The display adapters all share the same constructor & configuration
+// methods.
+macro_rules! impl_format_options {
+    ($($t:ident),* $(,)?) => {
+        $(
+            impl $t {
+                fn new(duration: Duration, negative: bool) -> $t {
+                    $t {
+                        duration,
+                        negative,
+                        options: FormatOptions::default(),
+                    }
+                }
+
+                /// Returns the sign to display before a value that has been
+                /// rounded to `rounded`
+                fn sign(&self, rounded: &Fixed) -> &'static str {
+                    if self.negative && !rounded.is_zero() {
+                        "-"
+                    } else {
+                        ""
+                    }
+                }
+
+                /// Display exactly `digits` fractional digits in the final
+                /// component.  A precision given in the format string (e.g.,
+                /// `{:.3}`) takes priority over this setting.
+                pub fn precision(mut self, digits: usize) -> Self {
+                    self.options.precision = Some(digits);
+                    self
+                }
+
+                /// Set how to round the final component when it has more
+                /// fractional digits than the precision allows.  The default
+                /// is [`Rounding::HalfUp`].
+                pub fn rounding(mut self, rounding: Rounding) -> Self {
+                    self.options.rounding = rounding;
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_format_options!(
+    DisplayAsSeconds,
+    DisplayHumanized,
+    DisplayClock,
+    DisplayIso8601,
+    DisplayGo
+);
+
+impl fmt::Display for DisplayHumanized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self
+            .options
+            .round(f, self.duration.as_nanos(), SECOND_SCALE);
+        let sign = self.sign(&secs);
+        let hms = Hms::new(secs.int);
+        let mut s = String::new();
+        for (qty, unit) in [(hms.days(), 'd'), (hms.hours % 24, 'h'), (hms.minutes, 'm')] {
+            if qty > 0 {
+                if !s.is_empty() {
+                    s.push(' ');
+                }
+                write!(s, "{qty}{unit}")?;
+            }
+        }
+        let secs = Fixed {
+            int: hms.seconds,
+            ..secs
+        };
+        if !secs.is_zero() || s.is_empty() {
+            if !s.is_empty() {
+                s.push(' ');
+            }
+            write!(s, "{secs}s")?;
+        }
+        s.insert_str(0, sign);
+        pad(f, &s)
+    }
+}
+
+impl fmt::Display for DisplayClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self
+            .options
+            .round(f, self.duration.as_nanos(), SECOND_SCALE);
+        let sign = self.sign(&secs);
+        let hms = Hms::new(secs.int);
+        let mut s = format!(
+            "{sign}{:02}:{:02}:{:02}",
+            hms.hours, hms.minutes, hms.seconds
+        );
+        secs.write_frac(&mut s)?;
+        pad(f, &s)
+    }
+}
+
+impl fmt::Display for DisplayIso8601 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self
+            .options
+            .round(f, self.duration.as_nanos(), SECOND_SCALE);
+        let sign = self.sign(&secs);
+        let hms = Hms::new(secs.int);
+        let mut s = format!("{sign}PT");
+        if hms.hours > 0 {
+            write!(s, "{}H", hms.hours)?;
+        }
+        if hms.minutes > 0 {
+            write!(s, "{}M", hms.minutes)?;
+        }
+        let secs = Fixed {
+            int: hms.seconds,
+            ..secs
+        };
+        if !secs.is_zero() || s.len() == sign.len() + 2 {
+            write!(s, "{secs}S")?;
+        }
+        pad(f, &s)
+    }
+}
+
+impl fmt::Display for DisplayGo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = self.duration.as_nanos();
+        if nanos == 0 {
+            return pad(f, "0s");
+        }
+        let mut scale = match nanos {
+            ..1_000 => 0,
+            1_000..1_000_000 => 3,
+            1_000_000..1_000_000_000 => 6,
+            _ => SECOND_SCALE,
+        };
+        // Rounding may carry a sub-second value up to 1000 of its unit, in
+        // which case we move up to the next unit, where the value is exact.
+        while scale < SECOND_SCALE {
+            let value = self.options.round(f, nanos, scale);
+            if value.int < 1000 {
+                let unit = match scale {
+                    0 => "ns",
+                    3 => "µs",
+                    _ => "ms",
+                };
+                return pad(f, &format!("{}{value}{unit}", self.sign(&value)));
+            }
+            scale += 3;
+        }
+        let secs = self.options.round(f, nanos, SECOND_SCALE);
+        let hms = Hms::new(secs.int);
+        let mut s = String::from(self.sign(&secs));
+        if hms.hours > 0 {
+            write!(s, "{}h{}m", hms.hours, hms.minutes)?;
+        } else if hms.minutes > 0 {
+            write!(s, "{}m", hms.minutes)?;
+        }
+        let secs = Fixed {
+            int: hms.seconds,
+            ..secs
+        };
+        write!(s, "{secs}s")?;
+        pad(f, &s)
+    }
+}
+
+/// The power of ten that converts nanoseconds to seconds
+const SECOND_SCALE: usize = 9;
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+struct FormatOptions {
+    precision: Option<usize>,
+    rounding: Rounding,
+}
+
+impl FormatOptions {
+    /// Convert `nanos` to a decimal number of units of `10^scale` nanoseconds
+    /// rounded according to the options & the precision in the format spec
+    fn round(&self, f: &fmt::Formatter<'_>, nanos: u128, scale: usize) -> Fixed {
+        Fixed::new(
+            nanos,
+            scale,
+            f.precision().or(self.precision),
+            self.rounding,
+        )
+    }
+}
+
+/// A nonnegative decimal number with at most nine significant fractional
+/// digits
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Fixed {
+    int: u128,
+    /// The fractional digits, as an integer less than `10^frac_digits`
+    frac: u32,
+    frac_digits: usize,
+    /// The number of zeros to display after `frac` in order to reach a
+    /// precision of more than nine digits
+    extra_zeros: usize,
+}
+
+impl Fixed {
+    /// Express `value` as a number of units of `10^scale`, where `scale <=
+    /// 9`, with the fractional part rounded to `precision` digits.  If
+    /// `precision` is `None`, the fractional part is given exactly with
+    /// trailing zeros removed.
+    fn new(value: u128, scale: usize, precision: Option<usize>, rounding: Rounding) -> Fixed {
+        let unit = u128::from(pow10(scale));
+        let mut int = value / unit;
+        let sub = u32::try_from(value % unit).expect("remainder should be less than 10^9");
+        match precision {
+            None => {
+                let mut frac = sub;
+                let mut frac_digits = scale;
+                while frac_digits > 0 && frac % 10 == 0 {
+                    frac /= 10;
+                    frac_digits -= 1;
+                }
+                Fixed {
+                    int,
+                    frac,
+                    frac_digits,
+                    extra_zeros: 0,
+                }
+            }
+            Some(p) if p >= scale => Fixed {
+                int,
+                frac: sub,
+                frac_digits: scale,
+                extra_zeros: p - scale,
+            },
+            Some(p) => {
+                let divisor = pow10(scale - p);
+                let mut frac = sub / divisor;
+                let rem = sub % divisor;
+                let round_up = match rounding {
+                    Rounding::Truncate => false,
+                    Rounding::Up => rem > 0,
+                    Rounding::HalfUp => rem >= divisor - rem,
+                    Rounding::HalfEven => {
+                        rem > divisor - rem || (rem == divisor - rem && frac % 2 == 1)
+                    }
+                };
+                if round_up {
+                    frac += 1;
+                    if frac == pow10(p) {
+                        frac = 0;
+                        int += 1;
+                    }
+                }
+                Fixed {
+                    int,
+                    frac,
+                    frac_digits: p,
+                    extra_zeros: 0,
+                }
+            }
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.int == 0 && self.frac == 0
+    }
+
+    /// Write the decimal point and fractional digits, if any
+    fn write_frac<W: Write>(&self, w: &mut W) -> fmt::Result {
+        if self.frac_digits + self.extra_zeros > 0 {
+            w.write_char('.')?;
+            if self.frac_digits > 0 {
+                write!(w, "{:0width$}", self.frac, width = self.frac_digits)?;
+            }
+            for _ in 0..self.extra_zeros {
+                w.write_char('0')?;
             }
         }
         Ok(())
     }
 }
 
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.int)?;
+        self.write_frac(f)
+    }
+}
+
+/// A number of seconds broken down into hours, minutes, and seconds
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Hms {
+    hours: u128,
+    minutes: u128,
+    seconds: u128,
+}
+
+impl Hms {
+    fn new(secs: u128) -> Hms {
+        Hms {
+            hours: secs / 3600,
+            minutes: secs % 3600 / 60,
+            seconds: secs % 60,
+        }
+    }
+
+    fn days(&self) -> u128 {
+        self.hours / 24
+    }
+}
+
+/// Returns 10 raised to the power `exp`, where `exp <= 9`
+fn pow10(exp: usize) -> u32 {
+    (0..exp).fold(1, |acc, _| acc * 10)
+}
+
+/// Write `s` to `f`, padded according to the width, fill, & alignment in the
+/// format spec.  Unlike [`fmt::Formatter::pad()`], this does not treat the
+/// precision as a maximum width.
+fn pad(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    let len = s.chars().count();
+    let padding = f.width().unwrap_or_default().saturating_sub(len);
+    let (before, after) = match f.align() {
+        None | Some(fmt::Alignment::Left) => (0, padding),
+        Some(fmt::Alignment::Right) => (padding, 0),
+        Some(fmt::Alignment::Center) => (padding / 2, padding.div_ceil(2)),
+    };
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(s)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +592,170 @@ mod tests {
         let d = Duration::new(secs, nanos);
         assert_eq!(d.display_as_seconds().to_string(), s);
     }
+
+    #[rstest]
+    #[case(0, 0, 3, "0.000")]
+    #[case(0, 0, 0, "0")]
+    #[case(1, 500_000_000, 0, "2")]
+    #[case(2, 500_000_000, 0, "3")]
+    #[case(9, 999_600_000, 3, "10.000")]
+    #[case(9, 999_400_000, 3, "9.999")]
+    #[case(0, 125_000_000, 2, "0.13")]
+    #[case(0, 1, 12, "0.000000001000")]
+    #[case(42, 0, 1, "42.0")]
+    fn test_precision(
+        #[case] secs: u64,
+        #[case] nanos: u32,
+        #[case] precision: usize,
+        #[case] s: &str,
+    ) {
+        let d = Duration::new(secs, nanos);
+        assert_eq!(format!("{:.*}", precision, d.display_as_seconds()), s);
+        assert_eq!(d.display_as_seconds().precision(precision).to_string(), s);
+    }
+
+    #[rstest]
+    #[case(Rounding::Truncate, "9.998")]
+    #[case(Rounding::Up, "9.999")]
+    #[case(Rounding::HalfUp, "9.999")]
+    #[case(Rounding::HalfEven, "9.998")]
+    fn test_rounding_modes(#[case] rounding: Rounding, #[case] s: &str) {
+        let d = Duration::new(9, 998_500_000);
+        assert_eq!(
+            format!("{:.3}", d.display_as_seconds().rounding(rounding)),
+            s
+        );
+    }
+
+    #[test]
+    fn test_width() {
+        let d = Duration::from_millis(1500);
+        assert_eq!(format!("{:>8.2}|", d.display_as_seconds()), "    1.50|");
+        assert_eq!(format!("{:<6}|", d.display_as_seconds()), "1.5   |");
+    }
+
+    #[rstest]
+    #[case(0, 0, "0s")]
+    #[case(0, 1, "0.000000001s")]
+    #[case(0, 250_000_000, "0.25s")]
+    #[case(59, 0, "59s")]
+    #[case(60, 0, "1m")]
+    #[case(3600, 0, "1h")]
+    #[case(3603, 0, "1h 3s")]
+    #[case(3723, 400_000_000, "1h 2m 3.4s")]
+    #[case(90061, 0, "1d 1h 1m 1s")]
+    #[case(86400, 500_000_000, "1d 0.5s")]
+    fn test_humanized(#[case] secs: u64, #[case] nanos: u32, #[case] s: &str) {
+        let d = Duration::new(secs, nanos);
+        assert_eq!(d.display_humanized().to_string(), s);
+    }
+
+    #[rstest]
+    #[case(0, 0, "00:00:00.000")]
+    #[case(3723, 400_000_000, "01:02:03.400")]
+    #[case(3723, 999_600_000, "01:02:04.000")]
+    #[case(3599, 999_600_000, "01:00:00.000")]
+    #[case(360_000, 0, "100:00:00.000")]
+    fn test_clock(#[case] secs: u64, #[case] nanos: u32, #[case] s: &str) {
+        let d = Duration::new(secs, nanos);
+        assert_eq!(d.display_clock().to_string(), s);
+    }
+
+    #[rstest]
+    #[case(0, 0, "PT0S")]
+    #[case(0, 500_000_000, "PT0.5S")]
+    #[case(60, 0, "PT1M")]
+    #[case(3723, 400_000_000, "PT1H2M3.4S")]
+    #[case(90000, 0, "PT25H")]
+    fn test_iso8601(#[case] secs: u64, #[case] nanos: u32, #[case] s: &str) {
+        let d = Duration::new(secs, nanos);
+        assert_eq!(d.display_iso8601().to_string(), s);
+    }
+
+    #[rstest]
+    #[case(0, 0, "0s")]
+    #[case(0, 1, "1ns")]
+    #[case(0, 1_500, "1.5µs")]
+    #[case(0, 1_500_000, "1.5ms")]
+    #[case(0, 999_999_999, "999.999999ms")]
+    #[case(1, 0, "1s")]
+    #[case(60, 0, "1m0s")]
+    #[case(3600, 0, "1h0m0s")]
+    #[case(3723, 400_000_000, "1h2m3.4s")]
+    #[case(90000, 0, "25h0m0s")]
+    fn test_go(#[case] secs: u64, #[case] nanos: u32, #[case] s: &str) {
+        let d = Duration::new(secs, nanos);
+        assert_eq!(d.display_go().to_string(), s);
+    }
+
+    #[rstest]
+    #[case(Rounding::Truncate, 2, "1.23s")]
+    #[case(Rounding::Up, 2, "1.24s")]
+    #[case(Rounding::HalfUp, 2, "1.24s")]
+    #[case(Rounding::HalfEven, 2, "1.24s")]
+    #[case(Rounding::Truncate, 3, "1.235s")]
+    #[case(Rounding::Up, 3, "1.235s")]
+    #[case(Rounding::HalfUp, 3, "1.235s")]
+    #[case(Rounding::HalfEven, 3, "1.235s")]
+    #[case(Rounding::Truncate, 0, "1s")]
+    #[case(Rounding::Up, 0, "2s")]
+    #[case(Rounding::HalfUp, 0, "1s")]
+    #[case(Rounding::HalfEven, 0, "1s")]
+    #[case(Rounding::HalfUp, 11, "1.23500000000s")]
+    fn test_rounding(#[case] rounding: Rounding, #[case] precision: usize, #[case] s: &str) {
+        let d = Duration::from_millis(1235);
+        let disp = d
+            .display_humanized()
+            .precision(precision)
+            .rounding(rounding);
+        assert_eq!(disp.to_string(), s);
+    }
+
+    #[rstest]
+    #[case(Rounding::Truncate, "0.12s")]
+    #[case(Rounding::Up, "0.13s")]
+    #[case(Rounding::HalfUp, "0.13s")]
+    #[case(Rounding::HalfEven, "0.12s")]
+    fn test_rounding_tie(#[case] rounding: Rounding, #[case] s: &str) {
+        let d = Duration::from_millis(125);
+        let disp = d.display_humanized().precision(2).rounding(rounding);
+        assert_eq!(disp.to_string(), s);
+    }
+
+    #[test]
+    fn test_rounding_carry() {
+        let d = Duration::new(59, 999_999_999);
+        assert_eq!(format!("{:.3}", d.display_humanized()), "1m");
+        assert_eq!(format!("{:.3}", d.display_iso8601()), "PT1M");
+        assert_eq!(format!("{:.3}", d.display_go()), "1m0.000s");
+        assert_eq!(format!("{:.3}", d.display_clock()), "00:01:00.000");
+        let d = Duration::from_nanos(999_999_500);
+        assert_eq!(format!("{:.2}", d.display_go()), "1.00s");
+        let d = Duration::from_nanos(999_500);
+        assert_eq!(format!("{:.0}", d.display_go()), "1ms");
+        let d = Duration::new(u64::MAX, 999_999_999);
+        assert_eq!(
+            format!("{:.0}", d.display_iso8601()),
+            "PT5124095576030431H16S"
+        );
+    }
+
+    #[test]
+    fn test_format_spec() {
+        let d = Duration::from_millis(3_723_400);
+        assert_eq!(format!("{:12}|", d.display_go()), "1h2m3.4s    |");
+        assert_eq!(format!("{:>12}|", d.display_go()), "    1h2m3.4s|");
+        assert_eq!(format!("{:*^13.2}|", d.display_go()), "**1h2m3.40s**|");
+        assert_eq!(format!("{:4}|", d.display_go()), "1h2m3.4s|");
+        assert_eq!(
+            format!("{:.1}", d.display_clock().precision(4)),
+            "01:02:03.4"
+        );
+        assert_eq!(d.display_clock().precision(0).to_string(), "01:02:03");
+        let d = Duration::from_micros(1);
+        assert_eq!(format!("{:>6}|", d.display_go()), "   1µs|");
+        assert_eq!(format!("{:.2}", d.display_go()), "1.00µs");
+        let d = Duration::from_nanos(7);
+        assert_eq!(format!("{:.2}", d.display_go()), "7.00ns");
+    }
 }
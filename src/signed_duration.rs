@@ -0,0 +1,338 @@
+use std::cmp::Ordering;
+use std::ops::{Add, Neg, Sub};
+use std::time::{Duration, Instant, SystemTime};
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// A span of time that may be negative, such as the difference between two
+/// timestamps.
+///
+/// A `SignedDuration` consists of a sign and a [`Duration`] magnitude, and so
+/// it can represent any value from `-Duration::MAX` to `Duration::MAX`,
+/// inclusive.  Zero is never negative.
+///
+/// `SignedDuration`s can be displayed using the adapters of
+/// [`DurationExt`](crate::show_duration::DurationExt).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SignedDuration {
+    negative: bool,
+    magnitude: Duration,
+}
+
+impl SignedDuration {
+    /// A duration of zero time
+    pub const ZERO: SignedDuration = SignedDuration {
+        negative: false,
+        magnitude: Duration::ZERO,
+    };
+
+    /// The largest representable duration, equal to `Duration::MAX`
+    pub const MAX: SignedDuration = SignedDuration {
+        negative: false,
+        magnitude: Duration::MAX,
+    };
+
+    /// The most negative representable duration, equal to `-Duration::MAX`
+    pub const MIN: SignedDuration = SignedDuration {
+        negative: true,
+        magnitude: Duration::MAX,
+    };
+
+    /// Construct a nonnegative `SignedDuration` with the given magnitude
+    pub fn positive(magnitude: Duration) -> SignedDuration {
+        SignedDuration {
+            negative: false,
+            magnitude,
+        }
+    }
+
+    /// Construct a nonpositive `SignedDuration` with the given magnitude
+    pub fn negative(magnitude: Duration) -> SignedDuration {
+        SignedDuration {
+            negative: !magnitude.is_zero(),
+            magnitude,
+        }
+    }
+
+    /// Returns `a - b`
+    pub fn diff(a: Duration, b: Duration) -> SignedDuration {
+        match a.checked_sub(b) {
+            Some(d) => SignedDuration::positive(d),
+            None => SignedDuration::negative(b.saturating_sub(a)),
+        }
+    }
+
+    /// Returns the amount of time from `start` to `end`, which is negative if
+    /// `end` is earlier than `start`
+    pub fn between(start: Instant, end: Instant) -> SignedDuration {
+        match end.checked_duration_since(start) {
+            Some(d) => SignedDuration::positive(d),
+            None => SignedDuration::negative(start.duration_since(end)),
+        }
+    }
+
+    /// Returns the amount of time from `start` to `end`, which is negative if
+    /// `end` is earlier than `start`
+    pub fn between_system_times(start: SystemTime, end: SystemTime) -> SignedDuration {
+        match end.duration_since(start) {
+            Ok(d) => SignedDuration::positive(d),
+            Err(e) => SignedDuration::negative(e.duration()),
+        }
+    }
+
+    /// Construct a `SignedDuration` from a number of nanoseconds.  Returns
+    /// `None` if the magnitude is too large for a [`Duration`].
+    pub fn from_nanos(nanos: i128) -> Option<SignedDuration> {
+        let magnitude = nanos.unsigned_abs();
+        let secs = u64::try_from(magnitude / NANOS_PER_SEC.unsigned_abs()).ok()?;
+        let subsec = u32::try_from(magnitude % NANOS_PER_SEC.unsigned_abs()).ok()?;
+        let magnitude = Duration::new(secs, subsec);
+        if nanos < 0 {
+            Some(SignedDuration::negative(magnitude))
+        } else {
+            Some(SignedDuration::positive(magnitude))
+        }
+    }
+
+    /// Returns the total number of nanoseconds in the duration
+    pub fn as_nanos(&self) -> i128 {
+        // `Duration::MAX.as_nanos()` is well within the range of `i128`.
+        let nanos = i128::try_from(self.magnitude.as_nanos()).unwrap_or(i128::MAX);
+        if self.negative { -nanos } else { nanos }
+    }
+
+    /// Returns the number of seconds in the duration as an `f64`
+    pub fn as_secs_f64(&self) -> f64 {
+        let secs = self.magnitude.as_secs_f64();
+        if self.negative { -secs } else { secs }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_zero()
+    }
+
+    /// Returns the absolute value of the duration
+    pub fn magnitude(&self) -> Duration {
+        self.magnitude
+    }
+
+    /// Returns the duration as a [`Duration`] if it is nonnegative
+    pub fn to_duration(&self) -> Option<Duration> {
+        (!self.negative).then_some(self.magnitude)
+    }
+
+    /// Returns `self + other`, or `None` if the magnitude of the result
+    /// would exceed `Duration::MAX`
+    pub fn checked_add(&self, other: SignedDuration) -> Option<SignedDuration> {
+        SignedDuration::from_nanos(self.as_nanos() + other.as_nanos())
+    }
+
+    /// Returns `self - other`, or `None` if the magnitude of the result
+    /// would exceed `Duration::MAX`
+    pub fn checked_sub(&self, other: SignedDuration) -> Option<SignedDuration> {
+        SignedDuration::from_nanos(self.as_nanos() - other.as_nanos())
+    }
+
+    /// Returns `self * rhs`, or `None` if the magnitude of the result would
+    /// exceed `Duration::MAX`
+    pub fn checked_mul(&self, rhs: i64) -> Option<SignedDuration> {
+        SignedDuration::from_nanos(self.as_nanos().checked_mul(i128::from(rhs))?)
+    }
+
+    /// Returns `self / rhs` rounded toward zero to the nearest nanosecond, or
+    /// `None` if `rhs` is zero
+    pub fn checked_div(&self, rhs: i64) -> Option<SignedDuration> {
+        SignedDuration::from_nanos(self.as_nanos().checked_div(i128::from(rhs))?)
+    }
+}
+
+impl From<Duration> for SignedDuration {
+    fn from(magnitude: Duration) -> SignedDuration {
+        SignedDuration::positive(magnitude)
+    }
+}
+
+impl Ord for SignedDuration {
+    fn cmp(&self, other: &SignedDuration) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for SignedDuration {
+    fn partial_cmp(&self, other: &SignedDuration) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for SignedDuration {
+    type Output = SignedDuration;
+
+    fn neg(self) -> SignedDuration {
+        if self.negative {
+            SignedDuration::positive(self.magnitude)
+        } else {
+            SignedDuration::negative(self.magnitude)
+        }
+    }
+}
+
+impl Add for SignedDuration {
+    type Output = SignedDuration;
+
+    fn add(self, other: SignedDuration) -> SignedDuration {
+        self.checked_add(other)
+            .expect("overflow when adding durations")
+    }
+}
+
+impl Sub for SignedDuration {
+    type Output = SignedDuration;
+
+    fn sub(self, other: SignedDuration) -> SignedDuration {
+        self.checked_sub(other)
+            .expect("overflow when subtracting durations")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::show_duration::DurationExt;
+    use rstest::rstest;
+
+    fn ms(millis: i64) -> SignedDuration {
+        SignedDuration::from_nanos(i128::from(millis) * 1_000_000).unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = Duration::from_millis(1500);
+        let b = Duration::from_millis(1625);
+        assert_eq!(SignedDuration::diff(a, b), ms(-125));
+        assert_eq!(SignedDuration::diff(b, a), ms(125));
+        assert_eq!(SignedDuration::diff(a, a), SignedDuration::ZERO);
+    }
+
+    #[test]
+    fn test_between() {
+        let start = Instant::now();
+        let end = start + Duration::from_secs(3);
+        assert_eq!(SignedDuration::between(start, end), ms(3000));
+        assert_eq!(SignedDuration::between(end, start), ms(-3000));
+        let start = SystemTime::UNIX_EPOCH;
+        let end = start + Duration::from_secs(3);
+        assert_eq!(SignedDuration::between_system_times(start, end), ms(3000));
+        assert_eq!(SignedDuration::between_system_times(end, start), ms(-3000));
+    }
+
+    #[test]
+    fn zero_is_not_negative() {
+        assert!(!SignedDuration::negative(Duration::ZERO).is_negative());
+        assert!(!(-SignedDuration::ZERO).is_negative());
+        assert_eq!(ms(5) - ms(5), SignedDuration::ZERO);
+        assert_eq!(SignedDuration::from_nanos(0), Some(SignedDuration::ZERO));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(ms(100) + ms(-250), ms(-150));
+        assert_eq!(ms(-100) - ms(-250), ms(150));
+        assert_eq!(-ms(100), ms(-100));
+        assert_eq!(ms(-100).checked_mul(-3), Some(ms(300)));
+        assert_eq!(
+            ms(-100).checked_div(8),
+            SignedDuration::from_nanos(-12_500_000)
+        );
+        assert_eq!(ms(-100).checked_div(0), None);
+        assert_eq!(ms(-100).to_duration(), None);
+        assert_eq!(ms(100).to_duration(), Some(Duration::from_millis(100)));
+        assert!((ms(-1500).as_secs_f64() + 1.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_checked_overflow() {
+        assert_eq!(SignedDuration::MAX.checked_add(ms(1)), None);
+        assert_eq!(SignedDuration::MIN.checked_sub(ms(1)), None);
+        assert_eq!(SignedDuration::MIN.checked_mul(2), None);
+        assert_eq!(SignedDuration::MAX.checked_mul(i64::MAX), None);
+        assert_eq!(-SignedDuration::MAX, SignedDuration::MIN);
+        assert_eq!(
+            SignedDuration::MIN.checked_add(SignedDuration::MAX),
+            Some(SignedDuration::ZERO)
+        );
+        assert_eq!(
+            SignedDuration::from_nanos(SignedDuration::MIN.as_nanos()),
+            Some(SignedDuration::MIN)
+        );
+        assert_eq!(
+            SignedDuration::from_nanos(SignedDuration::MIN.as_nanos() - 1),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow when adding durations")]
+    fn add_overflow() {
+        let _ = SignedDuration::MAX + ms(1);
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut values = vec![ms(5), ms(-5), SignedDuration::ZERO, ms(-10), ms(10)];
+        values.sort();
+        assert_eq!(
+            values,
+            [ms(-10), ms(-5), SignedDuration::ZERO, ms(5), ms(10)]
+        );
+        assert!(SignedDuration::MIN < SignedDuration::MAX);
+    }
+
+    #[rstest]
+    #[case(ms(-125), "-0.125", "-0.125s", "-00:00:00.125", "-PT0.125S", "-125ms")]
+    #[case(ms(125), "0.125", "0.125s", "00:00:00.125", "PT0.125S", "125ms")]
+    #[case(
+        ms(-3_723_400),
+        "-3723.4",
+        "-1h 2m 3.4s",
+        "-01:02:03.400",
+        "-PT1H2M3.4S",
+        "-1h2m3.4s"
+    )]
+    #[case(SignedDuration::ZERO, "0", "0s", "00:00:00.000", "PT0S", "0s")]
+    fn test_display(
+        #[case] d: SignedDuration,
+        #[case] seconds: &str,
+        #[case] humanized: &str,
+        #[case] clock: &str,
+        #[case] iso8601: &str,
+        #[case] go: &str,
+    ) {
+        assert_eq!(d.display_as_seconds().to_string(), seconds);
+        assert_eq!(d.display_humanized().to_string(), humanized);
+        assert_eq!(d.display_clock().to_string(), clock);
+        assert_eq!(d.display_iso8601().to_string(), iso8601);
+        assert_eq!(d.display_go().to_string(), go);
+    }
+
+    #[test]
+    fn display_rounded_to_zero() {
+        let d = SignedDuration::negative(Duration::from_micros(400));
+        assert_eq!(format!("{:.3}", d.display_as_seconds()), "0.000");
+        assert_eq!(format!("{:.3}", d.display_humanized()), "0.000s");
+        assert_eq!(format!("{:.3}", d.display_iso8601()), "PT0.000S");
+        assert_eq!(format!("{:.4}", d.display_as_seconds()), "-0.0004");
+        assert_eq!(
+            format!("{:>8.2}|", ms(-125).display_as_seconds()),
+            "   -0.13|"
+        );
+    }
+}